@@ -0,0 +1,116 @@
+use chrono::{Datelike as _, NaiveDate};
+use crate::{date_range::DateRange, recurrence::Recurrence};
+
+/// How days are counted when prorating a period.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum DayCount {
+	/// Exact days over the exact length of the period.
+	#[default]
+	Actual,
+	/// Exact days, each worth 1/365 of a year.
+	Actual365,
+	/// Exact days, each worth 1/360 of a year.
+	Actual360,
+	/// Every month has 30 days and the year 360, month ends count as the 30th (30E/360 ISDA),
+	/// so every month of a monthly plan costs the same.
+	Thirty360,
+}
+
+impl DayCount {
+	/// Days from `start` to `end` under this convention.
+	pub fn days(self, start: NaiveDate, end: NaiveDate) -> i64 {
+		match self {
+			Self::Actual | Self::Actual365 | Self::Actual360 => (end - start).num_days(),
+			Self::Thirty360 => {
+				let day = |date: NaiveDate| if date.succ_opt().is_none_or(|next| next.month() != date.month()) { 30 } else { date.day() };
+				let (d1, d2) = (day(start), day(end));
+				360 * i64::from(end.year() - start.year()) + 30 * (i64::from(end.month()) - i64::from(start.month())) + i64::from(d2) - i64::from(d1)
+			},
+		}
+	}
+}
+
+/// One billing cycle, from a renewal up to (not including) the next one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BillingPeriod {
+	pub start: NaiveDate,
+	pub end: NaiveDate,
+	pub recurrence: Recurrence,
+}
+
+impl BillingPeriod {
+	/// The monthly cycle renewing on `anchor`'s day of month that `date` falls in,
+	/// with the end-of-month clamping of [`crate::chrono_utils::ChronoNaiveDateExt::monthly_after`].
+	pub fn monthly(anchor: NaiveDate, date: NaiveDate) -> Self {
+		Self::containing(Recurrence::monthly(anchor), date).expect("date out of range")
+	}
+
+	/// The cycle `date` falls in, a renewal day starts a new one.
+	pub fn containing(recurrence: Recurrence, date: NaiveDate) -> Option<Self> {
+		Some(Self { start: recurrence.previous_before(date.succ_opt()?)?, end: recurrence.next_after(date)?, recurrence })
+	}
+
+	pub fn range(&self) -> DateRange { DateRange::new(self.start, self.end) }
+
+	pub fn days(&self) -> i64 { self.range().len() }
+
+	/// Days of the period before `date`, so a change on the renewal day has used none.
+	pub fn days_used(&self, date: NaiveDate) -> i64 { (date - self.start).num_days().clamp(0, self.days()) }
+
+	pub fn days_remaining(&self, date: NaiveDate) -> i64 { self.days() - self.days_used(date) }
+
+	/// Share of the period's price for the part from `date` to the end, e.g. the charge for an upgrade
+	/// or the credit for a cancellation. Times the price of the other plan for a switch.
+	///
+	/// The 365 and 360 day conventions price a day as a fixed share of a year, so for long months
+	/// the whole period comes out a bit over 1.
+	pub fn proration(&self, date: NaiveDate, day_count: DayCount) -> f64 {
+		let date = date.clamp(self.start, self.end);
+		let days = day_count.days(date, self.end) as f64;
+		match day_count {
+			DayCount::Actual => days / self.days() as f64,
+			DayCount::Actual365 => days / 365. * self.per_year(),
+			DayCount::Actual360 | DayCount::Thirty360 => days / 360. * self.per_year(),
+		}
+	}
+
+	/// How many periods make up a year.
+	fn per_year(&self) -> f64 {
+		match self.recurrence {
+			Recurrence::Weekly { interval, .. } => 365. / 7. / f64::from(interval),
+			Recurrence::Monthly { interval, .. } => 12. / f64::from(interval),
+			Recurrence::LastBusinessDay | Recurrence::NthWeekday { .. } => 12.,
+		}
+	}
+}
+
+#[test]
+fn proration() {
+	use crate::chrono_utils::date;
+	let approx = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+	let period = BillingPeriod::monthly(date(2023, 8, 31), date(2024, 2, 10));
+	assert_eq!((period.start, period.end), (date(2024, 1, 31), date(2024, 2, 29)));
+	assert_eq!((period.days(), period.days_used(date(2024, 2, 10)), period.days_remaining(date(2024, 2, 10))), (29, 10, 19));
+	assert_eq!(period.proration(date(2024, 2, 10), DayCount::Actual), 19. / 29.);
+	// february 29th counts as the 30th
+	assert!(approx(period.proration(date(2024, 2, 10), DayCount::Thirty360), 20. / 30.));
+	assert!(approx(period.proration(date(2024, 1, 31), DayCount::Thirty360), 1.));
+	assert!(approx(period.proration(date(2024, 2, 10), DayCount::Actual365), 19. * 12. / 365.));
+	assert!(approx(period.proration(date(2024, 2, 10), DayCount::Actual360), 19. / 30.));
+	assert_eq!(period.proration(date(2024, 1, 31), DayCount::Actual), 1.);
+	assert_eq!(period.proration(date(2024, 3, 10), DayCount::Actual), 0.);
+
+	// the renewal day belongs to the new period
+	let renewal = BillingPeriod::monthly(date(2023, 8, 31), date(2024, 2, 29));
+	assert_eq!((renewal.start, renewal.end), (date(2024, 2, 29), date(2024, 3, 31)));
+	assert_eq!(renewal.days_used(date(2024, 2, 29)), 0);
+	assert!(approx(renewal.proration(date(2024, 2, 29), DayCount::Thirty360), 1.));
+
+	let weekly = BillingPeriod::containing(Recurrence::weekly(date(2024, 1, 1)), date(2024, 1, 10)).unwrap();
+	assert_eq!(weekly.range(), DateRange::new(date(2024, 1, 8), date(2024, 1, 15)));
+	assert!(approx(weekly.proration(date(2024, 1, 11), DayCount::Actual365), 4. / 7.));
+
+	assert_eq!(DayCount::Thirty360.days(date(2024, 1, 31), date(2024, 3, 31)), 60);
+	assert_eq!(DayCount::Thirty360.days(date(2024, 2, 15), date(2024, 3, 1)), 16);
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+use chrono::{Datelike as _, Days, NaiveDate, Weekday};
+
+/// Which days are working days: everything but the weekend and holidays.
+///
+/// Deserializes from e.g. `{"weekend": ["Fri", "Sat"], "holidays": ["2024-12-25"]}`,
+/// missing fields default to a saturday/sunday weekend and no holidays.
+///
+/// The date methods panic if the weekend covers the whole week.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, smart_default::SmartDefault)]
+#[serde(default)]
+pub struct BusinessCalendar {
+	#[default(vec![Weekday::Sat, Weekday::Sun])]
+	pub weekend: Vec<Weekday>,
+	pub holidays: BTreeSet<NaiveDate>,
+}
+
+impl BusinessCalendar {
+	pub fn new(weekend: impl IntoIterator<Item = Weekday>, holidays: impl IntoIterator<Item = NaiveDate>) -> Self {
+		Self { weekend: weekend.into_iter().collect(), holidays: holidays.into_iter().collect() }
+	}
+
+	pub fn is_weekend(&self, date: NaiveDate) -> bool { self.weekend.contains(&date.weekday()) }
+
+	pub fn is_business_day(&self, date: NaiveDate) -> bool { !self.is_weekend(date) && !self.holidays.contains(&date) }
+
+	/// The first business day strictly after `date`.
+	pub fn next_business_day(&self, date: NaiveDate) -> NaiveDate { self.step(date, 1) }
+
+	/// The last business day strictly before `date`.
+	pub fn previous_business_day(&self, date: NaiveDate) -> NaiveDate { self.step(date, -1) }
+
+	/// `date` if it's a business day, otherwise the next one.
+	pub fn roll_forward(&self, date: NaiveDate) -> NaiveDate {
+		if self.is_business_day(date) { date } else { self.next_business_day(date) }
+	}
+
+	/// Moves `n` business days forward, or back for negative `n`. `date` itself doesn't have to be a business day,
+	/// adding 1 to a saturday gives monday.
+	pub fn add_business_days(&self, date: NaiveDate, n: i64) -> NaiveDate {
+		(0..n.unsigned_abs()).fold(date, |date, _| self.step(date, n.signum()))
+	}
+
+	/// Business days in `start..end`, negative if `end` is before `start`.
+	pub fn business_days_between(&self, start: NaiveDate, end: NaiveDate) -> i64 {
+		if end < start { return -self.business_days_between(end, start); }
+
+		let days = (end - start).num_days();
+		let weeks = days / 7;
+		// whole weeks have the same number of weekend days, only the rest needs walking
+		let rest_start = start.checked_add_days(Days::new(u64::try_from(weeks * 7).expect("end is after start"))).expect("date out of range");
+		let rest = rest_start.iter_days().take_while(|&date| date < end).filter(|&date| !self.is_weekend(date)).count();
+		let holidays = self.holidays.range(start..end).filter(|&&date| !self.is_weekend(date)).count();
+		let count = |n: usize| i64::try_from(n).expect("fewer days than fit in a date");
+		weeks * (7 - self.weekend_days()) + count(rest) - count(holidays)
+	}
+
+	fn weekend_days(&self) -> i64 {
+		let days = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun]
+			.into_iter()
+			.filter(|weekday| self.weekend.contains(weekday))
+			.count();
+		i64::try_from(days).expect("at most 7")
+	}
+
+	fn step(&self, mut date: NaiveDate, direction: i64) -> NaiveDate {
+		assert!(self.weekend_days() < 7, "business calendar has no business days");
+		loop {
+			date = if direction < 0 { date.pred_opt() } else { date.succ_opt() }.expect("date out of range");
+			if self.is_business_day(date) { return date; }
+		}
+	}
+}
+
+#[test]
+fn business_calendar() {
+	use crate::chrono_utils::date;
+
+	let calendar = serde_json::from_str::<BusinessCalendar>(r#"{"holidays": ["2024-12-25", "2024-12-26", "2024-12-28"]}"#).unwrap();
+	assert_eq!(calendar.weekend, vec![Weekday::Sat, Weekday::Sun]);
+
+	assert_eq!(date(2024, 12, 20).weekday(), Weekday::Fri);
+	assert!(calendar.is_business_day(date(2024, 12, 20)));
+	assert!(!calendar.is_business_day(date(2024, 12, 21)));
+	assert!(!calendar.is_business_day(date(2024, 12, 25)));
+	assert_eq!(calendar.next_business_day(date(2024, 12, 24)), date(2024, 12, 27));
+	assert_eq!(calendar.previous_business_day(date(2024, 12, 27)), date(2024, 12, 24));
+	assert_eq!(calendar.roll_forward(date(2024, 12, 21)), date(2024, 12, 23));
+	assert_eq!(calendar.roll_forward(date(2024, 12, 23)), date(2024, 12, 23));
+
+	assert_eq!(calendar.add_business_days(date(2024, 12, 21), 1), date(2024, 12, 23));
+	assert_eq!(calendar.add_business_days(date(2024, 12, 20), 3), date(2024, 12, 27));
+	assert_eq!(calendar.add_business_days(date(2024, 12, 27), -3), date(2024, 12, 20));
+	assert_eq!(calendar.add_business_days(date(2024, 12, 21), 0), date(2024, 12, 21));
+
+	// 23, 24, 27, 30, 31
+	assert_eq!(calendar.business_days_between(date(2024, 12, 21), date(2025, 1, 1)), 5);
+	assert_eq!(calendar.business_days_between(date(2025, 1, 1), date(2024, 12, 21)), -5);
+	assert_eq!(calendar.business_days_between(date(2024, 1, 1), date(2025, 1, 1)), 262 - 2);
+	for end in date(2024, 12, 1).iter_days().take(60) {
+		let slow = date(2024, 12, 1).iter_days().take_while(|&date| date < end).filter(|&date| calendar.is_business_day(date)).count();
+		assert_eq!(calendar.business_days_between(date(2024, 12, 1), end), i64::try_from(slow).unwrap());
+	}
+
+	let gulf = BusinessCalendar::new([Weekday::Fri, Weekday::Sat], []);
+	assert_eq!(gulf.next_business_day(date(2024, 12, 19)), date(2024, 12, 22));
+	assert_eq!(serde_json::to_string(&gulf).unwrap(), r#"{"weekend":["Fri","Sat"],"holidays":[]}"#);
+}
@@ -1,4 +1,5 @@
 use chrono::prelude::*;
+use crate::{billing::BillingPeriod, business_calendar::BusinessCalendar, date_range::DateRange};
 
 #[extend::ext(pub, name = ChronoNaiveDateExt)]
 impl chrono::NaiveDate {
@@ -14,8 +15,170 @@ impl chrono::NaiveDate {
 			(other.with_day0(0).unwrap() + chrono::Months::new(1)).pred_opt().unwrap()
 		}
 	}
+
+	fn is_business_day(&self, calendar: &BusinessCalendar) -> bool { calendar.is_business_day(*self) }
+	fn next_business_day(&self, calendar: &BusinessCalendar) -> chrono::NaiveDate { calendar.next_business_day(*self) }
+	fn previous_business_day(&self, calendar: &BusinessCalendar) -> chrono::NaiveDate { calendar.previous_business_day(*self) }
+	fn add_business_days(&self, n: i64, calendar: &BusinessCalendar) -> chrono::NaiveDate { calendar.add_business_days(*self, n) }
+	/// Business days in `self..other`, see [`BusinessCalendar::business_days_between`].
+	fn business_days_between(&self, other: chrono::NaiveDate, calendar: &BusinessCalendar) -> i64 { calendar.business_days_between(*self, other) }
+
+	/// `self..end`, iterate it with `.iter()` or `.step_by(chrono::Days::new(7))`.
+	fn until(&self, end: chrono::NaiveDate) -> DateRange { DateRange::new(*self, end) }
+	/// `self..=last`, up to the day before [`NaiveDate::MAX`], see [`DateRange::inclusive`].
+	fn through(&self, last: chrono::NaiveDate) -> DateRange { DateRange::inclusive(*self, last) }
+
+	// clamped for the partial weeks at either end of chrono's range
+	fn start_of_week(&self, week_start: Weekday) -> chrono::NaiveDate { self.week(week_start).checked_first_day().unwrap_or(chrono::NaiveDate::MIN) }
+	fn end_of_week(&self, week_start: Weekday) -> chrono::NaiveDate { self.week(week_start).checked_last_day().unwrap_or(chrono::NaiveDate::MAX) }
+	fn start_of_month(&self) -> chrono::NaiveDate { self.with_day(1).unwrap() }
+	// december ends with the year, there's no next month to step back from after the last one in range
+	fn end_of_month(&self) -> chrono::NaiveDate {
+		if self.month() == 12 { return self.end_of_year(); }
+		chrono::NaiveDate::from_ymd_opt(self.year(), self.month() + 1, 1).unwrap().pred_opt().unwrap()
+	}
+	fn start_of_quarter(&self) -> chrono::NaiveDate { self.with_day(1).unwrap().with_month0(self.month0() / 3 * 3).unwrap() }
+	fn end_of_quarter(&self) -> chrono::NaiveDate { self.start_of_quarter().with_month0(self.month0() / 3 * 3 + 2).unwrap().end_of_month() }
+	fn start_of_year(&self) -> chrono::NaiveDate { self.with_ordinal(1).unwrap() }
+	fn end_of_year(&self) -> chrono::NaiveDate { chrono::NaiveDate::from_ymd_opt(self.year(), 12, 31).unwrap() }
+
+	/// Monday to sunday of the ISO week this falls in.
+	fn iso_week_range(&self) -> DateRange { self.start_of_week(Weekday::Mon).through(self.end_of_week(Weekday::Mon)) }
+	/// `2025-W01`, the year is the ISO year so it's not always the calendar year.
+	fn iso_week_label(&self) -> String { format!("{}-W{:02}", self.iso_week().year(), self.iso_week().week()) }
+	/// 52 or 53, for the ISO year this falls in.
+	fn iso_weeks_in_year(&self) -> u32 {
+		chrono::NaiveDate::from_ymd_opt(self.iso_week().year(), 12, 28).unwrap().iso_week().week()
+	}
+
+	/// Whole months from `self` to `other`, negative if `other` is earlier. With the same end-of-month clamping
+	/// as [`monthly_after`](ChronoNaiveDateExt::monthly_after), so Jan 31 to Feb 29 is a month.
+	fn months_until(&self, other: chrono::NaiveDate) -> i32 {
+		if other < *self { return -other.months_until(*self); }
+		let months = (other.year() - self.year()) * 12 + other.month() as i32 - self.month() as i32;
+		if other.day() < self.day() && other != other.end_of_month() { months - 1 } else { months }
+	}
+
+	/// The monthly billing period renewing on `self`'s day of month that `date` falls in.
+	fn billing_period(&self, date: chrono::NaiveDate) -> BillingPeriod { BillingPeriod::monthly(*self, date) }
+
+	/// Whole years from `self` to `other`, e.g. an age. Someone born on Feb 29 gets older on Feb 28 in common years.
+	fn years_until(&self, other: chrono::NaiveDate) -> i32 { self.months_until(other) / 12 }
+}
+
+/// What to do with local times that happen twice (clocks go back) or not at all (clocks go forward).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct DstPolicy {
+	pub ambiguous: Ambiguous,
+	pub nonexistent: Nonexistent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Ambiguous {
+	/// The first time, still on the old offset.
+	#[default]
+	Earliest,
+	Latest,
+	Reject,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Nonexistent {
+	/// Keep the old offset, so 02:30 in a 02:00-03:00 gap becomes 03:30, like RFC 5545 does.
+	#[default]
+	ShiftForward,
+	/// The end of the gap, 02:30 becomes 03:00.
+	GapEnd,
+	Reject,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum LocalTimeError {
+	#[error("{0} happens twice in this timezone")]
+	Ambiguous(NaiveDateTime),
+	#[error("{0} doesn't exist in this timezone")]
+	Nonexistent(NaiveDateTime),
+	/// The recurrence has no day after this one, it's at the end of [`NaiveDate`]'s range or can never happen.
+	/// Also returned when adding months goes past the end of the range.
+	#[error("nothing recurs after {0}")]
+	NoOccurrence(NaiveDateTime),
+}
+
+impl DstPolicy {
+	pub fn resolve<Tz: TimeZone>(self, tz: &Tz, local: NaiveDateTime) -> Result<DateTime<Tz>, LocalTimeError> {
+		match tz.from_local_datetime(&local) {
+			chrono::LocalResult::Single(time) => Ok(time),
+			chrono::LocalResult::Ambiguous(earliest, latest) => match self.ambiguous {
+				Ambiguous::Earliest => Ok(earliest),
+				Ambiguous::Latest => Ok(latest),
+				Ambiguous::Reject => Err(LocalTimeError::Ambiguous(local)),
+			},
+			chrono::LocalResult::None => {
+				let nonexistent = || LocalTimeError::Nonexistent(local);
+				match self.nonexistent {
+					Nonexistent::ShiftForward => {
+						// gaps are at most a few hours, so this is before it
+						let before = tz.from_local_datetime(&(local - chrono::Duration::hours(6))).earliest().ok_or_else(nonexistent)?;
+						Ok(tz.from_utc_datetime(&(local - before.offset().fix())))
+					},
+					Nonexistent::GapEnd => {
+						// transitions are on whole minutes
+						let minute = local.with_second(0).and_then(|local| local.with_nanosecond(0)).ok_or_else(nonexistent)?;
+						(1..=6 * 60)
+							.find_map(|minutes| tz.from_local_datetime(&(minute + chrono::Duration::minutes(minutes))).earliest())
+							.ok_or_else(nonexistent)
+					},
+					Nonexistent::Reject => Err(nonexistent()),
+				}
+			},
+		}
+	}
+}
+
+/// [`ChronoNaiveDateExt`] for points in time, keeping the local wall-clock time in `self`'s timezone
+/// across DST changes and resolving the times that don't exist or happen twice with a [`DstPolicy`].
+#[extend::ext(pub, name = ChronoDateTimeExt)]
+impl<Tz: TimeZone> DateTime<Tz> {
+	/// The first time after `other` on `self`'s day of month and local time, clamped to the end of shorter months.
+	fn monthly_after<Tz2: TimeZone>(&self, other: &DateTime<Tz2>, policy: DstPolicy) -> Result<DateTime<Tz>, LocalTimeError> {
+		self.recurring_after(crate::recurrence::Recurrence::monthly(self.date_naive()), other, policy)
+	}
+
+	/// The first time after `other` on a day of `recurrence`, at `self`'s local time.
+	fn recurring_after<Tz2: TimeZone>(&self, recurrence: crate::recurrence::Recurrence, other: &DateTime<Tz2>, policy: DstPolicy) -> Result<DateTime<Tz>, LocalTimeError> {
+		let other = other.with_timezone(&self.timezone());
+		let time = self.time();
+		let start = other.date_naive();
+		let no_occurrence = || LocalTimeError::NoOccurrence(other.naive_local());
+		// the occurrence on other's day might still be ahead of it
+		let mut date = match start.succ_opt().and_then(|next| recurrence.previous_before(next)) {
+			Some(date) if date == start => date,
+			_ => recurrence.next_after(start).ok_or_else(no_occurrence)?,
+		};
+		loop {
+			let local = date.and_time(time);
+			match policy.resolve(&self.timezone(), local) {
+				Ok(candidate) if candidate > other => return Ok(candidate),
+				// a rejected time that's already past isn't the answer anyway
+				Err(error) if local >= other.naive_local() => return Err(error),
+				_ => {},
+			}
+			date = recurrence.next_after(date).ok_or_else(no_occurrence)?;
+		}
+	}
+
+	/// Adds calendar months keeping the local time, clamped to the end of shorter months.
+	fn add_months_local(&self, months: u32, policy: DstPolicy) -> Result<DateTime<Tz>, LocalTimeError> {
+		let local = self.naive_local();
+		let local = local.checked_add_months(chrono::Months::new(months)).ok_or(LocalTimeError::NoOccurrence(local))?;
+		policy.resolve(&self.timezone(), local)
+	}
 }
 
+/// Shorthand for tests, panics on dates that don't exist.
+#[cfg(test)]
+pub(crate) fn date(year: i32, month: u32, day: u32) -> NaiveDate { NaiveDate::from_ymd_opt(year, month, day).expect("valid date") }
+
 #[test]
 fn t1() {
 	let a = chrono::NaiveDate::from_ymd(2023, 5, 5);
@@ -79,3 +242,97 @@ fn t8() {
 	let c = chrono::NaiveDate::from_ymd(2025, 1, 15);
 	assert_eq!(a.monthly_after(b), c);
 }
+
+#[test]
+fn periods() {
+	let d = date(2024, 5, 15);
+	assert_eq!(d.weekday(), Weekday::Wed);
+
+	assert_eq!(d.start_of_week(Weekday::Mon), date(2024, 5, 13));
+	assert_eq!(d.end_of_week(Weekday::Mon), date(2024, 5, 19));
+	assert_eq!(d.start_of_week(Weekday::Sun), date(2024, 5, 12));
+	assert_eq!((d.start_of_month(), d.end_of_month()), (date(2024, 5, 1), date(2024, 5, 31)));
+	assert_eq!(date(2024, 2, 10).end_of_month(), date(2024, 2, 29));
+	assert_eq!((d.start_of_quarter(), d.end_of_quarter()), (date(2024, 4, 1), date(2024, 6, 30)));
+	assert_eq!(date(2024, 12, 31).start_of_quarter(), date(2024, 10, 1));
+	assert_eq!((d.start_of_year(), d.end_of_year()), (date(2024, 1, 1), date(2024, 12, 31)));
+
+	assert_eq!(date(2024, 12, 30).iso_week_label(), "2025-W01");
+	assert_eq!(date(2021, 1, 3).iso_week_label(), "2020-W53");
+	assert_eq!(date(2021, 1, 3).iso_weeks_in_year(), 53);
+	assert_eq!(d.iso_weeks_in_year(), 52);
+	assert_eq!(date(2024, 12, 30).iso_week_range(), date(2024, 12, 30).until(date(2025, 1, 6)));
+
+	// the last month, quarter and week of the range have no day after them
+	let max = NaiveDate::MAX;
+	assert_eq!((max.end_of_month(), max.end_of_quarter(), max.end_of_week(Weekday::Mon)), (max, max, max));
+	assert_eq!(NaiveDate::MIN.start_of_week(Weekday::Mon), NaiveDate::MIN);
+	assert_eq!(max.start_of_month().through(max), max.start_of_month().until(max));
+	assert_eq!(max.iso_week_range(), max.start_of_week(Weekday::Mon).until(max));
+
+	let weekly = date(2024, 1, 1).through(date(2024, 1, 29)).step_by(chrono::Days::new(7)).count();
+	assert_eq!(weekly, 5);
+	assert_eq!(date(2024, 1, 1).until(date(2024, 1, 29)).step_by(chrono::Days::new(7)).count(), 4);
+}
+
+#[test]
+fn years_until() {
+	let leapling = date(2000, 2, 29);
+
+	assert_eq!(leapling.years_until(date(2021, 2, 27)), 20);
+	assert_eq!(leapling.years_until(date(2021, 2, 28)), 21);
+	assert_eq!(leapling.years_until(date(2024, 2, 28)), 23);
+	assert_eq!(leapling.years_until(date(2024, 2, 29)), 24);
+	assert_eq!(date(1990, 6, 15).years_until(date(2024, 6, 14)), 33);
+	assert_eq!(date(1990, 6, 15).years_until(date(2024, 6, 15)), 34);
+	assert_eq!(date(2024, 6, 15).years_until(date(1990, 6, 15)), -34);
+
+	assert_eq!(date(2024, 1, 31).months_until(date(2024, 2, 29)), 1);
+	assert_eq!(date(2024, 1, 31).months_until(date(2024, 2, 28)), 0);
+	assert_eq!(date(2024, 1, 15).months_until(date(2024, 1, 14)), 0);
+}
+
+#[test]
+fn datetime_monthly_after() {
+	let berlin = chrono_tz::Europe::Berlin;
+	let local = |y, m, d, h, min| berlin.with_ymd_and_hms(y, m, d, h, min, 0).earliest().unwrap();
+	let utc = |y, m, d, h, min| Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap().with_timezone(&berlin);
+	let policy = DstPolicy::default();
+
+	// 09:00 stays 09:00 local when summer time starts, and the day is clamped like monthly_after
+	let anchor = local(2024, 1, 31, 9, 0);
+	assert_eq!(anchor.monthly_after(&local(2024, 2, 10, 0, 0), policy), Ok(local(2024, 2, 29, 9, 0)));
+	assert_eq!(anchor.monthly_after(&Utc.with_ymd_and_hms(2024, 3, 31, 6, 59, 0).unwrap(), policy), Ok(utc(2024, 3, 31, 7, 0)));
+	assert_eq!(anchor.monthly_after(&utc(2024, 3, 31, 7, 0), policy), Ok(local(2024, 4, 30, 9, 0)));
+	assert_eq!(anchor.add_months_local(2, policy), Ok(utc(2024, 3, 31, 7, 0)));
+	assert_eq!(anchor.add_months_local(u32::MAX, policy), Err(LocalTimeError::NoOccurrence(anchor.naive_local())));
+
+	// 02:30 doesn't exist on 2024-03-31
+	let anchor = local(2024, 1, 31, 2, 30);
+	let after = local(2024, 3, 15, 0, 0);
+	assert_eq!(anchor.monthly_after(&after, policy), Ok(utc(2024, 3, 31, 1, 30)));
+	let gap_end = DstPolicy { nonexistent: Nonexistent::GapEnd, ..policy };
+	assert_eq!(anchor.monthly_after(&after, gap_end), Ok(utc(2024, 3, 31, 1, 0)));
+	let strict = DstPolicy { ambiguous: Ambiguous::Reject, nonexistent: Nonexistent::Reject };
+	let nonexistent = date(2024, 3, 31).and_hms_opt(2, 30, 0).unwrap();
+	assert_eq!(anchor.monthly_after(&after, strict), Err(LocalTimeError::Nonexistent(nonexistent)));
+	assert_eq!(anchor.monthly_after(&local(2024, 3, 31, 12, 0), strict), Ok(local(2024, 4, 30, 2, 30)));
+
+	// and happens twice on 2024-10-27
+	let anchor = local(2024, 1, 27, 2, 30);
+	let after = local(2024, 10, 1, 0, 0);
+	assert_eq!(anchor.monthly_after(&after, policy), Ok(utc(2024, 10, 27, 0, 30)));
+	assert_eq!(anchor.monthly_after(&after, DstPolicy { ambiguous: Ambiguous::Latest, ..policy }), Ok(utc(2024, 10, 27, 1, 30)));
+	assert!(matches!(anchor.monthly_after(&after, strict), Err(LocalTimeError::Ambiguous(_))));
+	assert_eq!(anchor.monthly_after(&local(2024, 10, 27, 12, 0), strict), Ok(local(2024, 11, 27, 2, 30)));
+	assert_eq!(anchor.monthly_after(&utc(2024, 10, 27, 0, 30), policy), Ok(local(2024, 11, 27, 2, 30)));
+
+	// rules that never happen and the end of time are errors rather than panics
+	use crate::recurrence::Recurrence;
+	let never = [Recurrence::Weekly { anchor: date(2024, 1, 1), interval: 0 }, Recurrence::NthWeekday { n: 6, weekday: Weekday::Mon }];
+	for recurrence in never {
+		assert_eq!(anchor.recurring_after(recurrence, &after, policy), Err(LocalTimeError::NoOccurrence(after.naive_local())));
+	}
+	let end = NaiveDate::MAX.and_hms_opt(0, 0, 0).unwrap().and_utc();
+	assert_eq!(Utc.with_ymd_and_hms(2024, 1, 31, 9, 0, 0).unwrap().monthly_after(&end, policy), Err(LocalTimeError::NoOccurrence(end.naive_local())));
+}
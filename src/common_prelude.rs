@@ -19,11 +19,13 @@ pub use tap::prelude::*;
 pub use crate::default;
 pub use semver;
 pub use crate::hhmmss::Hhmmss;
+pub use crate::compound::DisplayCompound;
 pub use crate::spawn_complain;
 pub use log;
 pub use crate::logger::LogError;
 pub use crate::VerboseErrorForStatus;
 pub use crate::JoinHandleExt;
-pub use crate::chrono_utils::ChronoNaiveDateExt;
+pub use crate::CancellationToken;
+pub use crate::chrono_utils::{ChronoDateTimeExt, ChronoNaiveDateExt};
 pub use crate::boolExt;
-pub use crate::{dur, hmap, hset, hash};
+pub use crate::{dur, hmap, hset, hash, assert_logged};
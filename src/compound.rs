@@ -0,0 +1,123 @@
+//! Durations spelled out in several units, "1d 4h 3m 2s" or "2 weeks, 3 days", see [`CompoundFormat`].
+
+use crate::locale::{Form, Locale, Plural, TimeUnit};
+
+/// How [`DisplayCompound::display_compound_with`] writes a duration.
+/// Whatever doesn't fit in the smallest unit shown is truncated.
+#[derive(Clone, Copy, smart_default::SmartDefault)]
+pub struct CompoundFormat {
+	/// Consecutive units from the largest nonzero one, so 1h 0m 5s with 2 units is "1h".
+	#[default(usize::MAX)]
+	pub max_units: usize,
+	#[default(" ")]
+	pub separator: &'static str,
+	/// "3 hours" instead of "3h".
+	pub long: bool,
+	/// Skips units that come out 0, "1h 5s" instead of "1h 0m 5s".
+	#[default(true)]
+	pub omit_zeros: bool,
+	/// Decimals of the last unit, "1h 30.5m".
+	pub fraction_digits: usize,
+	/// Months have no fixed length, so they're never used.
+	#[default(TimeUnit::Day)]
+	pub largest: TimeUnit,
+	#[default(TimeUnit::Second)]
+	pub smallest: TimeUnit,
+	#[default(&crate::locale::English)]
+	pub locale: &'static dyn Locale,
+}
+
+pub trait DisplayCompound {
+	fn total_nanos(&self) -> i128;
+
+	/// Pretty-prints in the form `1d 4h 3m 2s`
+	fn display_compound(&self) -> String { self.display_compound_with(&CompoundFormat::default()) }
+
+	fn display_compound_with(&self, format: &CompoundFormat) -> String {
+		let total = self.total_nanos();
+		let (largest, smallest) = (format.largest.nanos().max(format.smallest.nanos()), format.smallest.nanos().min(format.largest.nanos()));
+		let units = TimeUnit::ALL
+			.into_iter()
+			.filter(|&unit| unit != TimeUnit::Month && (smallest..=largest).contains(&unit.nanos()))
+			.collect::<Vec<_>>();
+		if units.is_empty() { return String::new(); }
+		let first = units.iter().position(|&unit| total.abs() >= unit.nanos()).unwrap_or(units.len() - 1);
+		let window = &units[first..units.len().min(first.saturating_add(format.max_units.max(1)))];
+
+		let mut rest = total.unsigned_abs();
+		let mut parts = Vec::new();
+		let mut nonzero = false;
+		for (i, &unit) in window.iter().enumerate() {
+			let length = unit.nanos().unsigned_abs();
+			let value = rest / length;
+			rest %= length;
+			let is_last = i == window.len() - 1;
+			let digits = if is_last { format.fraction_digits.min(9) } else { 0 };
+			let fraction = rest * 10u128.pow(digits as u32) / length;
+			if value == 0 && fraction == 0 && format.omit_zeros && !(is_last && parts.is_empty()) { continue; }
+			nonzero |= value != 0 || fraction != 0;
+			if digits > 0 {
+				let number = format!("{value}{}{fraction:0digits$}", format.locale.decimal_separator());
+				parts.push(if format.long {
+					format!("{number} {}", format.locale.unit(unit, Plural::Other, Form::Plain))
+				} else {
+					format!("{number}{}", format.locale.short_unit(unit))
+				});
+			} else {
+				parts.push(format.locale.amount(value, unit, Form::Plain, !format.long));
+			}
+		}
+
+		// "-0s" when everything shown rounded away to zero
+		let sign = if total < 0 && nonzero { "-" } else { "" };
+		format!("{sign}{}", parts.join(format.separator))
+	}
+}
+
+impl DisplayCompound for chrono::Duration {
+	fn total_nanos(&self) -> i128 { i128::from(self.num_seconds()) * 1_000_000_000 + i128::from(self.subsec_nanos()) }
+}
+
+impl DisplayCompound for std::time::Duration {
+	fn total_nanos(&self) -> i128 { self.as_nanos() as i128 }
+}
+
+#[test]
+fn display_compound() {
+	let duration = chrono::Duration::days(1) + chrono::Duration::hours(4) + chrono::Duration::minutes(3) + chrono::Duration::seconds(2);
+	assert_eq!(duration.display_compound(), "1d 4h 3m 2s");
+	assert_eq!((-duration).display_compound(), "-1d 4h 3m 2s");
+	assert_eq!(chrono::Duration::milliseconds(-1).display_compound(), "0s");
+	assert_eq!(chrono::Duration::milliseconds(-1).display_compound_with(&CompoundFormat { fraction_digits: 3, ..crate::default() }), "-0.001s");
+	assert_eq!(chrono::Duration::milliseconds(-1).display_compound_with(&CompoundFormat { fraction_digits: 2, ..crate::default() }), "0.00s");
+	assert_eq!(duration.display_compound_with(&CompoundFormat { max_units: 2, ..crate::default() }), "1d 4h");
+	assert_eq!(chrono::Duration::hours(1).display_compound_with(&CompoundFormat { omit_zeros: false, ..crate::default() }), "1h 0m 0s");
+	assert_eq!(chrono::Duration::hours(1).display_compound(), "1h");
+
+	let long = CompoundFormat { separator: ", ", long: true, largest: TimeUnit::Week, ..crate::default() };
+	assert_eq!(chrono::Duration::days(17).display_compound_with(&long), "2 weeks, 3 days");
+	assert_eq!(chrono::Duration::days(8).display_compound_with(&long), "1 week, 1 day");
+	assert_eq!(chrono::Duration::zero().display_compound_with(&long), "0 seconds");
+	assert_eq!(std::time::Duration::from_millis(500).display_compound(), "0s");
+
+	let fractional = CompoundFormat { max_units: 2, fraction_digits: 1, ..crate::default() };
+	assert_eq!(std::time::Duration::from_secs(5430).display_compound_with(&fractional), "1h 30.5m");
+	assert_eq!(std::time::Duration::from_secs(3600).display_compound_with(&fractional), "1h");
+	assert_eq!(std::time::Duration::from_secs(3600).display_compound_with(&CompoundFormat { fraction_digits: 1, ..crate::default() }), "1h");
+	assert_eq!(std::time::Duration::from_millis(1250).display_compound_with(&fractional), "1.2s");
+	assert_eq!(
+		std::time::Duration::from_secs(5400).display_compound_with(&CompoundFormat { max_units: 1, fraction_digits: 1, long: true, ..crate::default() }),
+		"1.5 hours",
+	);
+	assert_eq!(
+		std::time::Duration::from_secs(5400)
+			.display_compound_with(&CompoundFormat { max_units: 1, fraction_digits: 1, long: true, locale: &crate::locale::Russian, ..crate::default() }),
+		"1,5 часа",
+	);
+	assert_eq!(
+		std::time::Duration::from_micros(1500).display_compound_with(&CompoundFormat { smallest: TimeUnit::Microsecond, ..crate::default() }),
+		"1ms 500µs",
+	);
+	let nanos = CompoundFormat { largest: TimeUnit::Nanosecond, smallest: TimeUnit::Nanosecond, ..crate::default() };
+	assert_eq!(chrono::Duration::days(300_000).display_compound_with(&nanos), "25920000000000000000ns");
+}
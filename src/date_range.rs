@@ -0,0 +1,99 @@
+use chrono::NaiveDate;
+
+/// Days from `start` up to but not including `end`, see [`DateRange::inclusive`] for `start..=last`.
+/// Ranges with `end <= start` are empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct DateRange {
+	pub start: NaiveDate,
+	pub end: NaiveDate,
+}
+
+impl DateRange {
+	pub fn new(start: NaiveDate, end: NaiveDate) -> Self { Self { start, end } }
+
+	/// `end` is exclusive and there's no day after [`NaiveDate::MAX`], so a range through it stops the day before.
+	pub fn inclusive(start: NaiveDate, last: NaiveDate) -> Self {
+		Self { start, end: last.succ_opt().unwrap_or(last) }
+	}
+
+	pub fn is_empty(&self) -> bool { self.end <= self.start }
+
+	/// Number of days.
+	pub fn len(&self) -> i64 { (self.end - self.start).num_days().max(0) }
+
+	/// The last day in the range, `None` if it's empty.
+	pub fn last(&self) -> Option<NaiveDate> { if self.is_empty() { None } else { self.end.pred_opt() } }
+
+	pub fn contains(&self, date: NaiveDate) -> bool { self.start <= date && date < self.end }
+
+	/// Whether every day of `other` is in `self`, always true for an empty `other`.
+	pub fn contains_range(&self, other: Self) -> bool {
+		other.is_empty() || (self.start <= other.start && other.end <= self.end)
+	}
+
+	pub fn overlaps(&self, other: Self) -> bool { self.intersection(other).is_some() }
+
+	/// The days in both, `None` if there are none.
+	pub fn intersection(&self, other: Self) -> Option<Self> {
+		let range = Self { start: self.start.max(other.start), end: self.end.min(other.end) };
+		(!range.is_empty()).then_some(range)
+	}
+
+	/// The days in either, `None` if there'd be a gap between them. Adjacent ranges merge.
+	pub fn union(&self, other: Self) -> Option<Self> {
+		if self.is_empty() { return Some(other); }
+		if other.is_empty() { return Some(*self); }
+		if self.start.max(other.start) > self.end.min(other.end) { return None; }
+		Some(Self { start: self.start.min(other.start), end: self.end.max(other.end) })
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = NaiveDate> + use<> { self.step_by(chrono::Days::new(1)) }
+
+	/// Every `step`th day starting at `start`.
+	pub fn step_by(&self, step: chrono::Days) -> impl Iterator<Item = NaiveDate> + use<> {
+		assert_ne!(step, chrono::Days::new(0), "step must be positive");
+		let end = self.end;
+		std::iter::successors(Some(self.start), move |date| date.checked_add_days(step)).take_while(move |date| *date < end)
+	}
+}
+
+impl IntoIterator for DateRange {
+	type Item = NaiveDate;
+	type IntoIter = Box<dyn Iterator<Item = NaiveDate>>;
+
+	fn into_iter(self) -> Self::IntoIter { Box::new(self.iter()) }
+}
+
+#[test]
+fn date_range() {
+	use crate::chrono_utils::date;
+
+	let january = DateRange::inclusive(date(2024, 1, 1), date(2024, 1, 31));
+	assert_eq!(january, DateRange::new(date(2024, 1, 1), date(2024, 2, 1)));
+	assert_eq!(january.len(), 31);
+	assert_eq!(january.last(), Some(date(2024, 1, 31)));
+	assert!(january.contains(date(2024, 1, 31)) && !january.contains(date(2024, 2, 1)));
+	assert_eq!(january.into_iter().count(), 31);
+	assert_eq!(january.step_by(chrono::Days::new(14)).collect::<Vec<_>>(), vec![date(2024, 1, 1), date(2024, 1, 15), date(2024, 1, 29)]);
+
+	let empty = DateRange::new(date(2024, 1, 10), date(2024, 1, 5));
+	assert!(empty.is_empty());
+	assert_eq!((empty.len(), empty.last(), empty.iter().count()), (0, None, 0));
+	assert!(january.contains_range(empty));
+
+	let mid = DateRange::new(date(2024, 1, 20), date(2024, 2, 10));
+	assert!(january.overlaps(mid));
+	assert!(!january.contains_range(mid));
+	assert_eq!(january.intersection(mid), Some(DateRange::new(date(2024, 1, 20), date(2024, 2, 1))));
+	assert_eq!(january.union(mid), Some(DateRange::new(date(2024, 1, 1), date(2024, 2, 10))));
+
+	let february = DateRange::inclusive(date(2024, 2, 1), date(2024, 2, 29));
+	assert!(!january.overlaps(february));
+	assert_eq!(january.intersection(february), None);
+	assert_eq!(january.union(february), Some(DateRange::new(date(2024, 1, 1), date(2024, 3, 1))));
+	assert_eq!(january.union(DateRange::new(date(2024, 2, 2), date(2024, 3, 1))), None);
+
+	let to_max = DateRange::inclusive(NaiveDate::MAX.pred_opt().unwrap(), NaiveDate::MAX);
+	assert_eq!((to_max.len(), to_max.last()), (1, NaiveDate::MAX.pred_opt()));
+	assert_eq!(to_max.iter().count(), 1);
+}
@@ -61,21 +61,55 @@ impl Duration {
 	}
 
 	/// Pretty formatting in the stlye of "1 Day", "3 hours", etc. - whatever the largest denominator is.
-	pub fn display_as_word(self) -> String {
+	pub fn display_as_word(self) -> String { self.display_as_word_in(&crate::locale::English) }
+
+	/// [`Duration::display_as_word`] in another language.
+	pub fn display_as_word_in(self, locale: &dyn crate::locale::Locale) -> String {
+		use crate::locale::{Form, TimeUnit};
+
 		[
-			(self.num_years_naive(),			   "year"),
-			(self.num_weeks(),					   "week"),
-			(self.num_days(),					   "day"),
-			(self.num_hours(),					   "hour"),
-			(self.num_minutes(),				   "minute"),
-			(self.num_seconds(),				   "second"),
-			(self.num_milliseconds(),			   "milisecond"),
-			(self.num_microseconds().unwrap_or(0), "microsecond"),
-			(self.num_nanoseconds().unwrap_or(0),  "nanosecond"),
+			(self.num_years_naive(),			   TimeUnit::Year),
+			(self.num_weeks(),					   TimeUnit::Week),
+			(self.num_days(),					   TimeUnit::Day),
+			(self.num_hours(),					   TimeUnit::Hour),
+			(self.num_minutes(),				   TimeUnit::Minute),
+			(self.num_seconds(),				   TimeUnit::Second),
+			(self.num_milliseconds(),			   TimeUnit::Millisecond),
+			(self.num_microseconds().unwrap_or(0), TimeUnit::Microsecond),
+			(self.num_nanoseconds().unwrap_or(0),  TimeUnit::Nanosecond),
 		].iter()
 			.find(|(x, _)| *x > 0)
-			.map_or("a moment".to_owned(), |(value, word)| format!("{value} {word}{}", if *value > 1 { "s" } else { "" }))
+			.map_or(locale.a_moment().to_owned(), |(value, unit)| locale.amount(value.unsigned_abs().into(), *unit, Form::Plain, false))
+	}
+}
+
+#[test]
+fn display_as_word() {
+	assert_eq!(Duration::milliseconds(5).display_as_word(), "5 milliseconds");
+	assert_eq!(Duration::days(1).display_as_word(), "1 day");
+	assert_eq!(Duration::seconds(-3).display_as_word(), "a moment");
+	assert_eq!(Duration::hours(21).display_as_word_in(&crate::locale::Russian), "21 час");
+	assert_eq!(Duration::weeks(3).display_as_word_in(&crate::locale::German), "3 Wochen");
+}
+
+/// See [`crate::duration_parse::parse`] for what's accepted.
+impl std::str::FromStr for Duration {
+	type Err = crate::duration_parse::ParseError;
+	fn from_str(s: &str) -> Result<Self, Self::Err> { crate::duration_parse::parse(s).map(Self) }
+}
+
+#[test]
+fn from_str() {
+	use crate::hhmmss::Hhmmss as _;
+
+	let duration = Duration::hours(26) + Duration::milliseconds(1500);
+	assert_eq!(duration.hhmmssxxx().parse(), Ok(duration));
+	assert_eq!((Duration::zero() - duration).hhmmssxxx().parse(), Ok(Duration::zero() - duration));
+	for duration in [Duration::milliseconds(5), Duration::days(1), Duration::weeks(3), Duration::days(730)] {
+		assert_eq!(duration.display_as_word().parse(), Ok(duration));
 	}
+	assert_eq!("PT1H30M".parse(), Ok(Duration::minutes(90)));
+	assert_eq!("1h30x".parse::<Duration>().unwrap_err().to_string(), "unknown unit \"x\" at 4");
 }
 
 impl TryFrom<std::time::Duration> for Duration {
@@ -91,6 +125,7 @@ impl TryFrom<Duration> for std::time::Duration {
 }
 
 impl crate::hhmmss::Hhmmss for Duration { fn sms(&self) -> (i64, i64) { self.0.sms() } }
+impl crate::compound::DisplayCompound for Duration { fn total_nanos(&self) -> i128 { self.0.total_nanos() } }
 
 impl std::ops::Mul<f32> for Duration {
 	type Output = Self;
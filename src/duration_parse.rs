@@ -0,0 +1,292 @@
+//! Reads durations back from what [`crate::hhmmss::Hhmmss`], [`crate::compound::DisplayCompound`] and
+//! `Duration::display_as_word` print, plus ISO 8601 ones, see [`parse`].
+
+use crate::locale::TimeUnit;
+
+const SECOND: i128 = 1_000_000_000;
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at {position}")]
+pub struct ParseError {
+	/// Byte offset into the input.
+	pub position: usize,
+	pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseErrorKind {
+	#[error("expected a number")]
+	ExpectedNumber,
+	#[error("expected two digits")]
+	ExpectedTwoDigits,
+	#[error("expected a unit")]
+	ExpectedUnit,
+	#[error("unknown unit {0:?}")]
+	UnknownUnit(String),
+	#[error("months have no fixed length")]
+	Months,
+	#[error("{0} must be below 60")]
+	TooLarge(&'static str),
+	#[error("unexpected {0:?}")]
+	Unexpected(char),
+	#[error("unexpected end")]
+	End,
+	#[error("out of range")]
+	OutOfRange,
+}
+
+/// Accepts, with an optional sign:
+/// - `HH:MM:SS` and `HH:MM:SS.fff`, hours can have any number of digits
+/// - units like `1h30m`, `1d 4h 3m 2s`, `1.5 hours` or `2 weeks, 3 days`
+/// - ISO 8601 like `PT1H30M` or `P1DT2.5S`, except months
+/// - "a moment", which is zero
+///
+/// Years are 365 days, as in `Duration::num_years_naive`.
+pub fn parse(input: &str) -> Result<chrono::Duration, ParseError> { parse_as(input, false) }
+
+/// Only the ISO 8601 form of [`parse`], `P1DT2H` or `-PT0.5S`.
+pub fn parse_iso8601(input: &str) -> Result<chrono::Duration, ParseError> { parse_as(input, true) }
+
+fn parse_as(input: &str, iso_only: bool) -> Result<chrono::Duration, ParseError> {
+	let mut cursor = Cursor { input, pos: 0 };
+	cursor.skip_whitespace();
+	let start = cursor.pos;
+	let negative = cursor.eat('-');
+	if !negative { cursor.eat('+'); }
+
+	let nanos = if cursor.eat('P') {
+		cursor.iso()?
+	} else if iso_only {
+		return Err(cursor.unexpected());
+	} else if cursor.rest().trim_end() == "a moment" {
+		cursor.pos = input.len();
+		0
+	} else if cursor.rest().contains(':') {
+		cursor.clock()?
+	} else {
+		cursor.units()?
+	};
+	cursor.skip_whitespace();
+	if cursor.peek().is_some() { return Err(cursor.unexpected()); }
+
+	let nanos = if negative { -nanos } else { nanos };
+	to_duration(nanos).ok_or(ParseError { position: start, kind: ParseErrorKind::OutOfRange })
+}
+
+pub(crate) fn to_duration(nanos: i128) -> Option<chrono::Duration> {
+	let seconds = chrono::Duration::try_seconds(i64::try_from(nanos.div_euclid(SECOND)).ok()?)?;
+	seconds.checked_add(&chrono::Duration::nanoseconds(nanos.rem_euclid(SECOND) as i64))
+}
+
+struct Cursor<'a> {
+	input: &'a str,
+	pos: usize,
+}
+
+/// `int.fraction`, `start` is where it begins for errors.
+struct Number<'a> {
+	start: usize,
+	int: &'a str,
+	fraction: &'a str,
+}
+
+impl Number<'_> {
+	fn nanos(&self, unit: TimeUnit) -> Result<i128, ParseError> {
+		let out_of_range = || ParseError { position: self.start, kind: ParseErrorKind::OutOfRange };
+		let length = unit.nanos();
+		let int = self.int.parse::<i128>().map_err(|_| out_of_range())?;
+		// more digits than that are below a nanosecond anyway
+		let fraction = &self.fraction[..self.fraction.len().min(18)];
+		let fraction = if fraction.is_empty() { 0 } else { fraction.parse::<i128>().map_err(|_| out_of_range())? * length / 10i128.pow(fraction.len() as u32) };
+		int.checked_mul(length).and_then(|n| n.checked_add(fraction)).ok_or_else(out_of_range)
+	}
+}
+
+impl<'a> Cursor<'a> {
+	fn rest(&self) -> &'a str { &self.input[self.pos..] }
+	fn peek(&self) -> Option<char> { self.rest().chars().next() }
+	fn error(&self, kind: ParseErrorKind) -> ParseError { ParseError { position: self.pos, kind } }
+
+	fn unexpected(&self) -> ParseError {
+		self.error(match self.peek() {
+			Some(c) => ParseErrorKind::Unexpected(c),
+			None => ParseErrorKind::End,
+		})
+	}
+
+	fn eat(&mut self, c: char) -> bool {
+		let ate = self.peek() == Some(c);
+		if ate { self.pos += c.len_utf8(); }
+		ate
+	}
+
+	fn expect(&mut self, c: char) -> Result<(), ParseError> { if self.eat(c) { Ok(()) } else { Err(self.unexpected()) } }
+
+	fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+		let rest = self.rest();
+		let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+		self.pos += len;
+		&rest[..len]
+	}
+
+	fn skip_whitespace(&mut self) { self.take_while(char::is_whitespace); }
+
+	fn digits(&mut self) -> Result<&'a str, ParseError> {
+		let digits = self.take_while(|c| c.is_ascii_digit());
+		if digits.is_empty() { return Err(self.error(ParseErrorKind::ExpectedNumber)); }
+		Ok(digits)
+	}
+
+	/// Digits with an optional `.` or `,` fraction.
+	fn number(&mut self) -> Result<Number<'a>, ParseError> {
+		let start = self.pos;
+		let int = self.digits()?;
+		let fraction = if self.rest().starts_with(['.', ',']) && self.rest()[1..].starts_with(|c: char| c.is_ascii_digit()) {
+			self.pos += 1;
+			self.digits()?
+		} else {
+			""
+		};
+		Ok(Number { start, int, fraction })
+	}
+
+	fn below_60(&mut self, what: &'static str) -> Result<&'a str, ParseError> {
+		let start = self.pos;
+		let digits = self.take_while(|c| c.is_ascii_digit());
+		if digits.len() != 2 { return Err(ParseError { position: start, kind: ParseErrorKind::ExpectedTwoDigits }); }
+		if digits >= "60" { return Err(ParseError { position: start, kind: ParseErrorKind::TooLarge(what) }); }
+		Ok(digits)
+	}
+
+	fn clock(&mut self) -> Result<i128, ParseError> {
+		let start = self.pos;
+		let hours = Number { start, int: self.digits()?, fraction: "" };
+		self.expect(':')?;
+		let minutes = self.below_60("minutes")?;
+		self.expect(':')?;
+		let seconds_start = self.pos;
+		let seconds = self.below_60("seconds")?;
+		let fraction = if self.eat('.') { self.digits()? } else { "" };
+
+		let minutes = i128::from(minutes.parse::<u8>().expect("two digits")) * TimeUnit::Minute.nanos();
+		let seconds = Number { start: seconds_start, int: seconds, fraction }.nanos(TimeUnit::Second)?;
+		hours.nanos(TimeUnit::Hour)?
+			.checked_add(minutes + seconds)
+			.ok_or(ParseError { position: start, kind: ParseErrorKind::OutOfRange })
+	}
+
+	fn unit(&mut self) -> Result<TimeUnit, ParseError> {
+		let start = self.pos;
+		let error = |kind| ParseError { position: start, kind };
+		Ok(match self.take_while(char::is_alphabetic) {
+			"" => return Err(error(ParseErrorKind::ExpectedUnit)),
+			"ns" | "nsec" | "nanosecond" | "nanoseconds" => TimeUnit::Nanosecond,
+			"us" | "µs" | "μs" | "usec" | "microsecond" | "microseconds" => TimeUnit::Microsecond,
+			"ms" | "msec" | "millisecond" | "milliseconds" => TimeUnit::Millisecond,
+			"s" | "sec" | "secs" | "second" | "seconds" => TimeUnit::Second,
+			"m" | "min" | "mins" | "minute" | "minutes" => TimeUnit::Minute,
+			"h" | "hr" | "hrs" | "hour" | "hours" => TimeUnit::Hour,
+			"d" | "day" | "days" => TimeUnit::Day,
+			"w" | "week" | "weeks" => TimeUnit::Week,
+			"y" | "year" | "years" => TimeUnit::Year,
+			"M" | "mo" | "month" | "months" => return Err(error(ParseErrorKind::Months)),
+			word => return Err(error(ParseErrorKind::UnknownUnit(word.to_owned()))),
+		})
+	}
+
+	/// `1h 30m`, `1h30m` or `2 weeks, 3 days`.
+	fn units(&mut self) -> Result<i128, ParseError> {
+		let mut total = 0i128;
+		loop {
+			let number = self.number()?;
+			self.skip_whitespace();
+			let unit = self.unit()?;
+			total = total.checked_add(number.nanos(unit)?).ok_or(ParseError { position: number.start, kind: ParseErrorKind::OutOfRange })?;
+
+			self.skip_whitespace();
+			if self.eat(',') {
+				self.skip_whitespace();
+				continue;
+			}
+			if !self.rest().starts_with(|c: char| c.is_ascii_digit()) { return Ok(total); }
+		}
+	}
+
+	/// What follows the `P` of `P1DT2H`, fractions are allowed on any part.
+	fn iso(&mut self) -> Result<i128, ParseError> {
+		let mut total = 0i128;
+		let mut time = false;
+		let mut rank = 0;
+		let mut parts = 0;
+		loop {
+			if !time && self.eat('T') {
+				time = true;
+				parts = 0;
+				continue;
+			}
+			if !self.rest().starts_with(|c: char| c.is_ascii_digit()) { break; }
+
+			let number = self.number()?;
+			let Some(designator) = self.peek() else { return Err(self.error(ParseErrorKind::ExpectedUnit)) };
+			let (unit, order) = match (time, designator) {
+				(false, 'Y') => (TimeUnit::Year, 1),
+				(false, 'M') => return Err(self.error(ParseErrorKind::Months)),
+				(false, 'W') => (TimeUnit::Week, 2),
+				(false, 'D') => (TimeUnit::Day, 3),
+				(true, 'H') => (TimeUnit::Hour, 4),
+				(true, 'M') => (TimeUnit::Minute, 5),
+				(true, 'S') => (TimeUnit::Second, 6),
+				_ => return Err(self.unexpected()),
+			};
+			if order <= rank { return Err(self.unexpected()); }
+			self.pos += 1;
+			rank = order;
+			parts += 1;
+			total = total.checked_add(number.nanos(unit)?).ok_or(ParseError { position: number.start, kind: ParseErrorKind::OutOfRange })?;
+		}
+		// `P` and `PT` alone say nothing
+		if parts == 0 { return Err(self.error(ParseErrorKind::ExpectedNumber)); }
+		Ok(total)
+	}
+}
+
+#[test]
+fn parse_duration() {
+	use chrono::Duration;
+	let error = |position, kind| Err(ParseError { position, kind });
+
+	assert_eq!(parse("01:02:03"), Ok(Duration::seconds(3723)));
+	assert_eq!(parse("-00:00:01.500"), Ok(Duration::milliseconds(-1500)));
+	assert_eq!(parse("100:00:00"), Ok(Duration::hours(100)));
+	assert_eq!(parse("1h30m"), Ok(Duration::minutes(90)));
+	assert_eq!(parse(" 1d 4h 3m 2s "), Ok(Duration::seconds(86400 + 4 * 3600 + 3 * 60 + 2)));
+	assert_eq!(parse("2 weeks, 3 days"), Ok(Duration::days(17)));
+	assert_eq!(parse("1.5 hours"), Ok(Duration::minutes(90)));
+	assert_eq!(parse("1h 30.5m"), Ok(Duration::seconds(5430)));
+	assert_eq!(parse("5 milliseconds"), Ok(Duration::milliseconds(5)));
+	assert_eq!(parse("250µs"), Ok(Duration::microseconds(250)));
+	assert_eq!(parse("1 year"), Ok(Duration::days(365)));
+	assert_eq!(parse("a moment"), Ok(Duration::zero()));
+	assert_eq!(parse("PT1H30M"), Ok(Duration::minutes(90)));
+	assert_eq!(parse("P1DT2H"), Ok(Duration::hours(26)));
+	assert_eq!(parse("-P1W"), Ok(Duration::weeks(-1)));
+	assert_eq!(parse("PT0,25S"), Ok(Duration::milliseconds(250)));
+	assert_eq!(parse("PT0.000000001S"), Ok(Duration::nanoseconds(1)));
+
+	assert_eq!(parse(""), error(0, ParseErrorKind::ExpectedNumber));
+	assert_eq!(parse("1h 30x"), error(5, ParseErrorKind::UnknownUnit("x".into())));
+	assert_eq!(parse("1h 30"), error(5, ParseErrorKind::ExpectedUnit));
+	assert_eq!(parse("3 months"), error(2, ParseErrorKind::Months));
+	assert_eq!(parse("1h, "), error(4, ParseErrorKind::ExpectedNumber));
+	assert_eq!(parse("01:60:00"), error(3, ParseErrorKind::TooLarge("minutes")));
+	assert_eq!(parse("01:2:00"), error(3, ParseErrorKind::ExpectedTwoDigits));
+	assert_eq!(parse("01:02"), error(5, ParseErrorKind::End));
+	assert_eq!(parse("01:02:03 x"), error(9, ParseErrorKind::Unexpected('x')));
+	assert_eq!(parse("P1M"), error(2, ParseErrorKind::Months));
+	assert_eq!(parse("PT1S2H"), error(5, ParseErrorKind::Unexpected('H')));
+	assert_eq!(parse("PT"), error(2, ParseErrorKind::ExpectedNumber));
+	assert_eq!(parse("P1H"), error(2, ParseErrorKind::Unexpected('H')));
+	assert_eq!(parse_iso8601("1h"), error(0, ParseErrorKind::Unexpected('1')));
+	assert_eq!(parse("99999999999999999999999h"), error(0, ParseErrorKind::OutOfRange));
+	assert_eq!(parse("999999999999999h"), error(0, ParseErrorKind::OutOfRange));
+}
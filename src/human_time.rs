@@ -0,0 +1,394 @@
+//! Dates and times for people: parsing `tomorrow 9am`, `in 3 days` or `next friday`, and formatting "3 hours ago".
+
+use chrono::{DateTime, Datelike as _, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use crate::{chrono_utils::{DstPolicy, LocalTimeError}, locale::{English, Form, Locale, TimeUnit}};
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+	#[error("don't know what {0:?} means")]
+	Unrecognized(String),
+	#[error("{0:?} is out of range")]
+	OutOfRange(String),
+	#[error(transparent)]
+	LocalTime(#[from] LocalTimeError),
+}
+
+enum Parsed {
+	Local(NaiveDateTime),
+	Fixed(DateTime<FixedOffset>),
+	/// Calendar months and days move the local time, the rest is exact.
+	Relative { months: i64, days: i64, exact: chrono::Duration },
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+	Second,
+	Minute,
+	Hour,
+	Day,
+	Week,
+	Month,
+	Year,
+}
+
+/// Parses `input` relative to `now`. Understands
+/// - `now`, `2024-05-01`, `2024-05-01 14:30`, and RFC 3339 (keeping its local time)
+/// - `today`, `tomorrow`, `yesterday`, `friday` or `next friday` (the first one after today) and `last friday`,
+///   at midnight unless followed by a time
+/// - times like `9am`, `9:30 pm`, `14:00`, `noon` or `at midnight`, on their own they're today
+/// - `in 3 days`, `in an hour`, `2 weeks ago`, `in 1h 30m`, `next month`, `last year`, keeping the time of `now`
+///
+/// Months and years are calendar ones, clamped to the end of shorter months.
+pub fn parse(input: &str, now: NaiveDateTime) -> Result<NaiveDateTime, ParseError> {
+	match parse_inner(input, now.date())? {
+		Parsed::Local(local) => Ok(local),
+		Parsed::Fixed(fixed) => Ok(fixed.naive_local()),
+		Parsed::Relative { months, days, exact } => shift(now, months, days)
+			.and_then(|local| local.checked_add_signed(exact))
+			.ok_or_else(|| ParseError::OutOfRange(input.to_owned())),
+	}
+}
+
+/// [`parse`] in `now`'s timezone. Hours and smaller count real time across DST changes while days keep
+/// the local time, local times that don't exist or happen twice get the default [`DstPolicy`].
+pub fn parse_in<Tz: TimeZone>(input: &str, now: &DateTime<Tz>) -> Result<DateTime<Tz>, ParseError> {
+	let tz = now.timezone();
+	match parse_inner(input, now.date_naive())? {
+		Parsed::Local(local) => Ok(DstPolicy::default().resolve(&tz, local)?),
+		Parsed::Fixed(fixed) => Ok(fixed.with_timezone(&tz)),
+		Parsed::Relative { months, days, exact } => {
+			let out_of_range = || ParseError::OutOfRange(input.to_owned());
+			let shifted = if months == 0 && days == 0 {
+				now.clone()
+			} else {
+				DstPolicy::default().resolve(&tz, shift(now.naive_local(), months, days).ok_or_else(out_of_range)?)?
+			};
+			shifted.checked_add_signed(exact).ok_or_else(out_of_range)
+		},
+	}
+}
+
+fn parse_inner(input: &str, today: NaiveDate) -> Result<Parsed, ParseError> {
+	let trimmed = input.trim();
+	if let Ok(fixed) = DateTime::parse_from_rfc3339(trimmed) { return Ok(Parsed::Fixed(fixed)); }
+	for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+		if let Ok(local) = NaiveDateTime::parse_from_str(trimmed, format) { return Ok(Parsed::Local(local)); }
+	}
+
+	let lower = trimmed.to_lowercase();
+	let tokens = lower.split(|c: char| c.is_whitespace() || c == ',').filter(|token| !token.is_empty()).collect::<Vec<_>>();
+	let unrecognized = || ParseError::Unrecognized(input.to_owned());
+
+	let relative = match tokens.as_slice() {
+		["now"] => Ok(Some(Parsed::Relative { months: 0, days: 0, exact: chrono::Duration::zero() })),
+		["in", amounts @ ..] => parse_amounts(amounts, 1),
+		[amounts @ .., "ago"] => parse_amounts(amounts, -1),
+		["next", unit] if parse_unit(unit).is_some() => parse_amounts(&["1", *unit], 1),
+		["last", unit] if parse_unit(unit).is_some() => parse_amounts(&["1", *unit], -1),
+		_ => Ok(None),
+	}
+	.map_err(|OutOfRange| ParseError::OutOfRange(input.to_owned()))?;
+	if let Some(relative) = relative { return Ok(relative); }
+
+	let (date, rest) = match tokens.as_slice() {
+		["today", rest @ ..] => (Some(today), rest),
+		["tomorrow", rest @ ..] => (today.succ_opt(), rest),
+		["yesterday", rest @ ..] => (today.pred_opt(), rest),
+		["next", weekday, rest @ ..] if parse_weekday(weekday).is_some() => (next_weekday(today, parse_weekday(weekday).unwrap()), rest),
+		["last", weekday, rest @ ..] if parse_weekday(weekday).is_some() => (last_weekday(today, parse_weekday(weekday).unwrap()), rest),
+		[weekday, rest @ ..] if parse_weekday(weekday).is_some() => (next_weekday(today, parse_weekday(weekday).unwrap()), rest),
+		[date, rest @ ..] if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok() => (NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(), rest),
+		rest => (None, rest),
+	};
+	let rest = rest.strip_prefix(&["at"]).unwrap_or(rest);
+
+	match (date, rest) {
+		(Some(date), []) => Ok(Parsed::Local(date.and_time(NaiveTime::MIN))),
+		(date, time) if !time.is_empty() => {
+			let time = time_of_day(&time.concat()).ok_or_else(unrecognized)?;
+			Ok(Parsed::Local(date.unwrap_or(today).and_time(time)))
+		},
+		_ => Err(unrecognized()),
+	}
+}
+
+/// Amounts that are too large for a [`chrono::Duration`] or a month count, as opposed to ones that don't parse.
+struct OutOfRange;
+
+/// `3 days`, `an hour`, `1h 30m`, `2 hours and 5 minutes`. `None` if `tokens` aren't amounts.
+fn parse_amounts(tokens: &[&str], sign: i64) -> Result<Option<Parsed>, OutOfRange> {
+	let (mut months, mut days, mut exact) = (0_i64, 0_i64, chrono::Duration::zero());
+	let mut tokens = tokens.iter().filter(|token| **token != "and").peekable();
+	if tokens.peek().is_none() { return Ok(None); }
+
+	while let Some(token) = tokens.next() {
+		let (amount, unit) = match token.find(|c: char| !c.is_ascii_digit()) {
+			// 3d, 30m
+			Some(split) if split > 0 => {
+				let Some(unit) = parse_unit(&token[split..]) else { return Ok(None) };
+				(number(&token[..split])?, unit)
+			},
+			_ => {
+				let amount = match *token {
+					"a" | "an" => 1,
+					amount if amount.bytes().all(|b| b.is_ascii_digit()) => number(amount)?,
+					_ => return Ok(None),
+				};
+				let Some(unit) = tokens.next().and_then(|unit| parse_unit(unit)) else { return Ok(None) };
+				(amount, unit)
+			},
+		};
+		let amount = amount.checked_mul(sign).ok_or(OutOfRange)?;
+		match unit {
+			Unit::Second => exact = exact.checked_add(&chrono::Duration::try_seconds(amount).ok_or(OutOfRange)?).ok_or(OutOfRange)?,
+			Unit::Minute => exact = exact.checked_add(&chrono::Duration::try_minutes(amount).ok_or(OutOfRange)?).ok_or(OutOfRange)?,
+			Unit::Hour => exact = exact.checked_add(&chrono::Duration::try_hours(amount).ok_or(OutOfRange)?).ok_or(OutOfRange)?,
+			Unit::Day => days = days.checked_add(amount).ok_or(OutOfRange)?,
+			Unit::Week => days = amount.checked_mul(7).and_then(|amount| days.checked_add(amount)).ok_or(OutOfRange)?,
+			Unit::Month => months = months.checked_add(amount).ok_or(OutOfRange)?,
+			Unit::Year => months = amount.checked_mul(12).and_then(|amount| months.checked_add(amount)).ok_or(OutOfRange)?,
+		}
+	}
+	Ok(Some(Parsed::Relative { months, days, exact }))
+}
+
+// only called on ascii digits, so the only way to fail is being too large
+fn number(digits: &str) -> Result<i64, OutOfRange> { digits.parse().map_err(|_| OutOfRange) }
+
+fn shift(local: NaiveDateTime, months: i64, days: i64) -> Option<NaiveDateTime> {
+	let abs = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+	let local = if months >= 0 { local.checked_add_months(abs)? } else { local.checked_sub_months(abs)? };
+	local.checked_add_signed(chrono::Duration::try_days(days)?)
+}
+
+fn parse_unit(s: &str) -> Option<Unit> {
+	Some(match s {
+		"s" | "sec" | "secs" | "second" | "seconds" => Unit::Second,
+		"m" | "min" | "mins" | "minute" | "minutes" => Unit::Minute,
+		"h" | "hr" | "hrs" | "hour" | "hours" => Unit::Hour,
+		"d" | "day" | "days" => Unit::Day,
+		"w" | "wk" | "wks" | "week" | "weeks" => Unit::Week,
+		"mo" | "month" | "months" => Unit::Month,
+		"y" | "yr" | "yrs" | "year" | "years" => Unit::Year,
+		_ => return None,
+	})
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> { s.parse().ok() }
+
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+	std::iter::successors(today.succ_opt(), NaiveDate::succ_opt).find(|date| date.weekday() == weekday)
+}
+
+fn last_weekday(today: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+	std::iter::successors(today.pred_opt(), NaiveDate::pred_opt).find(|date| date.weekday() == weekday)
+}
+
+/// `9am`, `9:30pm`, `14:00`, `14:00:30`, `noon`, `midnight`
+fn time_of_day(s: &str) -> Option<NaiveTime> {
+	match s {
+		"noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+		"midnight" => return Some(NaiveTime::MIN),
+		_ => {},
+	}
+
+	let (clock, pm) = match (s.strip_suffix("am"), s.strip_suffix("pm")) {
+		(Some(clock), _) => (clock, Some(false)),
+		(_, Some(clock)) => (clock, Some(true)),
+		_ => (s, None),
+	};
+	let mut parts = clock.split(':').map(|part| part.parse::<u32>().ok());
+	let hour = parts.next()??;
+	let (minute, second) = (parts.next().unwrap_or(Some(0))?, parts.next().unwrap_or(Some(0))?);
+	if parts.next().is_some() { return None; }
+
+	let hour = match pm {
+		Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+		Some(_) => return None,
+		// a bare number is too ambiguous
+		None if !clock.contains(':') => return None,
+		None => hour,
+	};
+	NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+/// How to round the smallest unit shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Rounding {
+	/// 1 day 20 hours is "1 day"
+	#[default]
+	Floor,
+	/// 1 day 20 hours is "2 days", 1 day 11 hours is "1 day"
+	Round,
+	/// 1 day 1 hour is "2 days"
+	Ceil,
+}
+
+/// Formats durations as "3 hours ago" or "in 2 days".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, smart_default::SmartDefault)]
+pub struct RelativeFormat {
+	/// How many units to show at most, 2 gives "1 day 4 hours".
+	#[default(1)]
+	pub precision: usize,
+	pub rounding: Rounding,
+	/// "3h ago" instead of "3 hours ago".
+	pub short: bool,
+}
+
+// the units a relative time is spelled out in
+const UNITS: [TimeUnit; 7] = [TimeUnit::Year, TimeUnit::Month, TimeUnit::Week, TimeUnit::Day, TimeUnit::Hour, TimeUnit::Minute, TimeUnit::Second];
+
+impl RelativeFormat {
+	/// `time` as seen from `now`, "in 5 minutes" if it's later.
+	pub fn between<Tz: TimeZone, Tz2: TimeZone>(&self, time: &DateTime<Tz>, now: &DateTime<Tz2>) -> String {
+		self.between_in(time, now, &English)
+	}
+
+	pub fn between_in<Tz: TimeZone, Tz2: TimeZone>(&self, time: &DateTime<Tz>, now: &DateTime<Tz2>, locale: &dyn Locale) -> String {
+		self.duration_in(time.clone().signed_duration_since(now.clone()), locale)
+	}
+
+	/// "in 2 days" for positive durations, "2 days ago" for negative ones, "just now" under a second.
+	pub fn duration(&self, duration: chrono::Duration) -> String { self.duration_in(duration, &English) }
+
+	pub fn duration_in(&self, duration: chrono::Duration, locale: &dyn Locale) -> String {
+		let past = duration < chrono::Duration::zero();
+		let span = self.span_as(duration, locale, if past { Form::Past } else { Form::Future });
+		if span.is_empty() { locale.just_now().to_owned() } else if past { locale.past(&span) } else { locale.future(&span) }
+	}
+
+	/// Just the amount, "1 day 4 hours". Empty under a second.
+	pub fn span(&self, duration: chrono::Duration) -> String { self.span_in(duration, &English) }
+
+	pub fn span_in(&self, duration: chrono::Duration, locale: &dyn Locale) -> String { self.span_as(duration, locale, Form::Plain) }
+
+	fn span_as(&self, duration: chrono::Duration, locale: &dyn Locale, form: Form) -> String {
+		let seconds = |unit: TimeUnit| i64::try_from(unit.nanos() / TimeUnit::Second.nanos()).expect("whole seconds");
+		let total = duration.num_seconds().abs();
+		let Some(largest) = UNITS.iter().position(|&unit| total >= seconds(unit)) else { return String::new() };
+		let last = (largest + self.precision.max(1) - 1).min(UNITS.len() - 1);
+
+		// the units don't divide each other, so split first and only round what's left below the last one
+		let mut rest = total;
+		let mut amounts = UNITS[..=last].iter().map(|&unit| {
+			let n = rest / seconds(unit);
+			rest %= seconds(unit);
+			(unit, n)
+		}).collect::<Vec<_>>();
+		let round_up = match self.rounding {
+			Rounding::Floor => false,
+			Rounding::Round => rest * 2 >= seconds(UNITS[last]),
+			Rounding::Ceil => rest > 0,
+		};
+		if round_up {
+			amounts[last].1 += 1;
+			// carries up as far as rounding filled a bigger unit, 12 months make a year even though they're 360 days
+			for i in (1..=last).rev() {
+				let (unit, n) = amounts[i];
+				let per_parent = if unit == TimeUnit::Month { 12 } else { (seconds(UNITS[i - 1]) + seconds(unit) - 1) / seconds(unit) };
+				if n < per_parent { break; }
+				amounts[i].1 = 0;
+				amounts[i - 1].1 += 1;
+			}
+		}
+
+		let mut parts = Vec::new();
+		for (unit, n) in amounts {
+			if n == 0 || parts.len() == self.precision.max(1) { continue; }
+			parts.push(locale.amount(n.unsigned_abs().into(), unit, form, self.short));
+		}
+		parts.join(" ")
+	}
+}
+
+/// `time` as seen from `now` with the default [`RelativeFormat`], e.g. "3 hours ago".
+pub fn relative<Tz: TimeZone, Tz2: TimeZone>(time: &DateTime<Tz>, now: &DateTime<Tz2>) -> String {
+	RelativeFormat::default().between(time, now)
+}
+
+#[test]
+fn parse_human() {
+	use crate::chrono_utils::date;
+
+	let now = date(2024, 5, 15).and_hms_opt(10, 20, 0).unwrap();
+	assert_eq!(now.weekday(), chrono::Weekday::Wed);
+	let at = |m, d, h, min| date(2024, m, d).and_hms_opt(h, min, 0).unwrap();
+
+	for (input, expected) in [
+		("now", now),
+		("tomorrow 9am", at(5, 16, 9, 0)),
+		("Tomorrow at 9:30 PM", at(5, 16, 21, 30)),
+		("yesterday", at(5, 14, 0, 0)),
+		("noon", at(5, 15, 12, 0)),
+		("14:45", at(5, 15, 14, 45)),
+		("friday", at(5, 17, 0, 0)),
+		("next wednesday", at(5, 22, 0, 0)),
+		("last friday at 14:30", at(5, 10, 14, 30)),
+		("2024-05-01", at(5, 1, 0, 0)),
+		("2024-05-01 9 pm", at(5, 1, 21, 0)),
+		("2024-05-01 14:30", at(5, 1, 14, 30)),
+		("2024-05-01T12:00:00+02:00", at(5, 1, 12, 0)),
+		("in 3 days", at(5, 18, 10, 20)),
+		("in an hour", at(5, 15, 11, 20)),
+		("in 1h 30m", at(5, 15, 11, 50)),
+		("2 weeks and 1 day ago", at(4, 30, 10, 20)),
+		("next week", at(5, 22, 10, 20)),
+		("last month", at(4, 15, 10, 20)),
+	] {
+		assert_eq!(parse(input, now), Ok(expected), "{input}");
+	}
+
+	for input in ["", "blah", "13pm", "9", "in 3 fortnights", "tomorrow tomorrow", "3 days"] {
+		assert_eq!(parse(input, now), Err(ParseError::Unrecognized(input.to_owned())), "{input}");
+	}
+	for input in ["in 99999999999 years", "in 9999999999999999999999 days", "9999999999999999 hours ago", "in 1999999999999999999 weeks"] {
+		assert_eq!(parse(input, now), Err(ParseError::OutOfRange(input.to_owned())), "{input}");
+	}
+
+	// berlin switches to summer time on the night of the 31st
+	let now = chrono_tz::Europe::Berlin.with_ymd_and_hms(2024, 3, 30, 12, 0, 0).unwrap();
+	let utc = |d, h| chrono::Utc.with_ymd_and_hms(2024, 3, d, h, 0, 0).unwrap();
+	assert_eq!(parse_in("in 1 day", &now).unwrap(), utc(31, 10));
+	assert_eq!(parse_in("in 24 hours", &now).unwrap(), utc(31, 11));
+	assert_eq!(parse_in("tomorrow 2:30am", &now).unwrap(), utc(31, 1) + chrono::Duration::minutes(30));
+	assert_eq!(parse_in("2024-03-30T12:00:00Z", &now).unwrap(), utc(30, 12));
+}
+
+#[test]
+fn relative_format() {
+	let minutes = chrono::Duration::minutes;
+	let day_and_20h = chrono::Duration::hours(44);
+
+	assert_eq!(RelativeFormat::default().duration(-minutes(180)), "3 hours ago");
+	assert_eq!(RelativeFormat::default().duration(day_and_20h), "in 1 day");
+	assert_eq!(RelativeFormat::default().duration(chrono::Duration::milliseconds(-300)), "just now");
+	assert_eq!(RelativeFormat { rounding: Rounding::Round, ..crate::default() }.duration(day_and_20h), "in 2 days");
+	assert_eq!(RelativeFormat { rounding: Rounding::Ceil, ..crate::default() }.duration(minutes(61)), "in 2 hours");
+	assert_eq!(RelativeFormat { precision: 2, ..crate::default() }.duration(-day_and_20h), "1 day 20 hours ago");
+	assert_eq!(RelativeFormat { precision: 2, short: true, ..crate::default() }.duration(-day_and_20h), "1d 20h ago");
+	assert_eq!(RelativeFormat { precision: 3, ..crate::default() }.span(minutes(60 * 24 + 5)), "1 day 5 minutes");
+	// rounding carries into bigger units
+	assert_eq!(RelativeFormat { precision: 2, rounding: Rounding::Round, ..crate::default() }.span(minutes(119) + chrono::Duration::seconds(40)), "2 hours");
+	assert_eq!(RelativeFormat::default().span(chrono::Duration::days(400)), "1 year");
+	// a month is 30 days and a year 365, floor doesn't turn 12 months into a year but rounding up does
+	assert_eq!(RelativeFormat::default().duration(chrono::Duration::days(-364)), "12 months ago");
+	assert_eq!(RelativeFormat::default().duration(chrono::Duration::days(-359)), "11 months ago");
+	assert_eq!(RelativeFormat { rounding: Rounding::Round, ..crate::default() }.span(chrono::Duration::days(350)), "1 year");
+	let two = RelativeFormat { precision: 2, ..crate::default() };
+	assert_eq!(two.span(chrono::Duration::days(750)), "2 years");
+	assert_eq!(two.span(chrono::Duration::days(395)), "1 year 1 month");
+	assert_eq!(two.span(chrono::Duration::days(37)), "1 month 1 week");
+	assert_eq!(two.span(chrono::Duration::days(5 * 365 + 60)), "5 years 2 months");
+	assert_eq!(RelativeFormat { rounding: Rounding::Round, ..two }.span(chrono::Duration::days(365 + 29) + chrono::Duration::hours(23)), "1 year 1 month");
+	assert_eq!(RelativeFormat { rounding: Rounding::Ceil, ..crate::default() }.span(chrono::Duration::days(6) + chrono::Duration::hours(1)), "1 week");
+
+	let now = chrono::Utc.with_ymd_and_hms(2024, 5, 15, 12, 0, 0).unwrap();
+	assert_eq!(relative(&(now - chrono::Duration::days(14)), &now), "2 weeks ago");
+	assert_eq!(relative(&now.with_timezone(&chrono_tz::Asia::Tokyo), &now), "just now");
+
+	let format = RelativeFormat { precision: 2, ..crate::default() };
+	assert_eq!(format.duration_in(-day_and_20h, &crate::locale::German), "vor 1 Tag 20 Stunden");
+	assert_eq!(format.span_in(day_and_20h, &crate::locale::German), "1 Tag 20 Stunden");
+	assert_eq!(format.duration_in(minutes(61), &crate::locale::Russian), "через 1 час 1 минуту");
+	assert_eq!(RelativeFormat { short: true, ..format }.duration_in(-minutes(61), &crate::locale::Russian), "1 ч 1 мин назад");
+}
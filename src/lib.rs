@@ -7,20 +7,101 @@ pub mod common_prelude;
 pub mod logger;
 pub mod math;
 pub mod hhmmss;
+pub mod compound;
+pub mod duration_parse;
 pub mod chrono_utils;
+pub mod business_calendar;
+pub mod date_range;
+pub mod billing;
+pub mod human_time;
+pub mod locale;
+pub mod recurrence;
+pub mod rrule;
+pub mod time;
+#[cfg(not(target_arch = "wasm32"))] pub mod task_group;
+#[cfg(not(target_arch = "wasm32"))] pub mod supervisor;
+#[cfg(not(target_arch = "wasm32"))] pub mod shutdown;
+#[cfg(not(target_arch = "wasm32"))] pub mod scheduler;
 
 #[cfg(feature = "custom_duration")] pub use duration::Duration;
+#[cfg(not(target_arch = "wasm32"))] pub use task_group::TaskGroup;
+#[cfg(not(target_arch = "wasm32"))] pub use supervisor::{spawn_supervised, RestartPolicy};
+#[cfg(not(target_arch = "wasm32"))] pub use shutdown::Shutdown;
+#[cfg(not(target_arch = "wasm32"))] pub use scheduler::{Schedule, Scheduler};
+pub use tokio_util::sync::{CancellationToken, DropGuard};
 use common_prelude::*;
 
 pub static REQWEST_CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
 
-pub struct AbortOnDrop<T>(tokio::task::JoinHandle<T>);
-impl<T> Drop for AbortOnDrop<T> { fn drop(&mut self) { self.0.abort(); } }
+// `None` only once `detach` took the handle
+pub struct AbortOnDrop<T>(
+	Option<tokio::task::JoinHandle<T>>,
+	#[expect(dead_code, reason = "only held to stop the `abort_on_cancel` watcher when dropped")] Option<DropGuard>,
+);
+impl<T> Drop for AbortOnDrop<T> { fn drop(&mut self) { if let Some(handle) = &self.0 { handle.abort(); } } }
+
+impl<T> AbortOnDrop<T> {
+	pub fn is_finished(&self) -> bool { self.handle().is_finished() }
+	pub fn abort(&self) { self.handle().abort(); }
+
+	/// Disarms the abort, the task keeps running after the returned handle is dropped or the token is cancelled.
+	pub fn detach(mut self) -> tokio::task::JoinHandle<T> { self.0.take().expect("not detached yet") }
+
+	fn handle(&self) -> &tokio::task::JoinHandle<T> { self.0.as_ref().expect("not detached yet") }
+}
+
+impl<T> std::future::Future for AbortOnDrop<T> {
+	type Output = Result<T, tokio::task::JoinError>;
+	fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
+		std::pin::Pin::new(self.0.as_mut().expect("not detached yet")).poll(cx)
+	}
+}
+
+/// Aborts every task still in it when dropped. Yields task results in the order they complete.
+pub struct AbortOnDropSet<T>(stream::FuturesUnordered<AbortOnDrop<T>>);
+
+impl<T> AbortOnDropSet<T> {
+	#[must_use]
+	pub fn new() -> Self { Self(stream::FuturesUnordered::new()) }
+	pub fn push(&mut self, task: AbortOnDrop<T>) { self.0.push(task); }
+	pub fn len(&self) -> usize { self.0.len() }
+	pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+	/// `None` once the set is empty.
+	pub async fn join_next(&mut self) -> Option<Result<T, tokio::task::JoinError>> { self.0.next().await }
+}
+
+impl<T> Default for AbortOnDropSet<T> { fn default() -> Self { Self::new() } }
+
+impl<T> Stream for AbortOnDropSet<T> {
+	type Item = Result<T, tokio::task::JoinError>;
+	fn poll_next(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Option<Self::Item>> {
+		std::pin::Pin::new(&mut self.0).poll_next(cx)
+	}
+}
+
+impl<T> FromIterator<AbortOnDrop<T>> for AbortOnDropSet<T> {
+	fn from_iter<I: IntoIterator<Item = AbortOnDrop<T>>>(iter: I) -> Self { Self(iter.into_iter().collect()) }
+}
+
+impl<T> Extend<AbortOnDrop<T>> for AbortOnDropSet<T> {
+	fn extend<I: IntoIterator<Item = AbortOnDrop<T>>>(&mut self, iter: I) { self.0.extend(iter); }
+}
 
 #[extend::ext(pub, name = JoinHandleExt)]
 impl<T> tokio::task::JoinHandle<T> {
 	fn abort_on_drop(self) -> AbortOnDrop<T> {
-		AbortOnDrop(self)
+		AbortOnDrop(Some(self), None)
+	}
+
+	/// [`JoinHandleExt::abort_on_drop`] that also aborts the task once `token` or any of its parents is cancelled.
+	fn abort_on_cancel(self, token: CancellationToken) -> AbortOnDrop<T> {
+		let (abort, stop) = (self.abort_handle(), CancellationToken::new());
+		let watcher = stop.clone();
+		tokio::task::spawn(async move {
+			if watcher.run_until_cancelled(token.cancelled()).await.is_some() { abort.abort(); }
+		});
+		AbortOnDrop(Some(self), Some(stop.drop_guard()))
 	}
 }
 
@@ -32,24 +113,115 @@ impl bool {
 #[cfg(target_arch = "wasm32")]
 #[track_caller]
 pub fn spawn_complain<T>(x: impl std::future::Future<Output = anyhow::Result<T>> + 'static) {
-	wasm_bindgen_futures::spawn_local(async move { if let Err(e) = x.await { log::error!("{e:?}"); } });
+	wasm_bindgen_futures::spawn_local(complain(async move { x.await.map(drop) }, SpawnSite::caller(None)));
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 #[track_caller]
 pub fn spawn_complain<T>(x: impl std::future::Future<Output = anyhow::Result<T>> + 'static) {
-	tokio::task::spawn_local(async move { if let Err(e) = x.await { log::error!("{e:?}"); } });
+	tokio::task::spawn_local(complain(async move { x.await.map(drop) }, SpawnSite::caller(None)));
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 #[track_caller]
 pub fn spawn_complain_send<T>(x: impl std::future::Future<Output = anyhow::Result<T>> + Send + 'static) {
-	tokio::task::spawn(async move { if let Err(e) = x.await { log::error!("{e:?}"); } });
+	tokio::task::spawn(complain(async move { x.await.map(drop) }, SpawnSite::caller(None)));
+}
+
+/// [`spawn_complain`] with a name to tell the task apart in logs and sentry.
+#[cfg(target_arch = "wasm32")]
+#[track_caller]
+pub fn spawn_complain_named<T>(name: impl Into<String>, x: impl std::future::Future<Output = anyhow::Result<T>> + 'static) {
+	wasm_bindgen_futures::spawn_local(complain(async move { x.await.map(drop) }, SpawnSite::caller(Some(name.into()))));
+}
+
+/// [`spawn_complain`] with a name to tell the task apart in logs and sentry.
+#[cfg(not(target_arch = "wasm32"))]
+#[track_caller]
+pub fn spawn_complain_named<T>(name: impl Into<String>, x: impl std::future::Future<Output = anyhow::Result<T>> + 'static) {
+	tokio::task::spawn_local(complain(async move { x.await.map(drop) }, SpawnSite::caller(Some(name.into()))));
+}
+
+/// [`spawn_complain_send`] with a name to tell the task apart in logs and sentry.
+#[cfg(not(target_arch = "wasm32"))]
+#[track_caller]
+pub fn spawn_complain_send_named<T>(name: impl Into<String>, x: impl std::future::Future<Output = anyhow::Result<T>> + Send + 'static) {
+	tokio::task::spawn(complain(async move { x.await.map(drop) }, SpawnSite::caller(Some(name.into()))));
+}
+
+/// [`spawn_complain`] that drops the task once `token` or any of its parents is cancelled.
+///
+/// For tasks that should wind down on their own instead, pass them a [`CancellationToken::child_token`]
+/// and `select!` on [`CancellationToken::cancelled`].
+#[track_caller]
+pub fn spawn_complain_until<T>(token: CancellationToken, x: impl std::future::Future<Output = anyhow::Result<T>> + 'static) {
+	spawn_complain(async move { token.run_until_cancelled_owned(x).await.transpose() });
+}
+
+/// [`spawn_complain_send`] that drops the task once `token` or any of its parents is cancelled.
+#[cfg(not(target_arch = "wasm32"))]
+#[track_caller]
+pub fn spawn_complain_send_until<T>(token: CancellationToken, x: impl std::future::Future<Output = anyhow::Result<T>> + Send + 'static) {
+	spawn_complain_send(async move { token.run_until_cancelled_owned(x).await.transpose() });
+}
+
+/// Where a task was spawned from, for blaming it when it fails.
+pub(crate) struct SpawnSite {
+	name: Option<String>,
+	location: &'static std::panic::Location<'static>,
+}
+
+impl SpawnSite {
+	#[track_caller]
+	pub(crate) fn caller(name: Option<String>) -> Self {
+		Self { name, location: std::panic::Location::caller() }
+	}
+}
+
+impl std::fmt::Display for SpawnSite {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.name {
+			Some(name) => write!(f, "task {name} spawned at {}", self.location),
+			None => write!(f, "task spawned at {}", self.location),
+		}
+	}
+}
+
+// the spawned JoinHandle is dropped, so a panic would otherwise vanish without a trace
+pub(crate) async fn complain(x: impl std::future::Future<Output = anyhow::Result<()>>, site: SpawnSite) {
+	let e = match std::panic::AssertUnwindSafe(x).catch_unwind().await {
+		Ok(Ok(())) => return,
+		Ok(Err(e)) => e.context(format!("{site} failed")),
+		Err(panic) => anyhow::anyhow!("{site} panicked: {}", panic_message(&*panic)),
+	};
+
+	let mut tags: Vec<(&str, &dyn std::fmt::Display)> = vec![("spawned_at", &site.location)];
+	if let Some(name) = &site.name { tags.push(("task", name)); }
+	report_error(&e, &tags);
+}
+
+/// Logs `e` and sends its whole chain to sentry with `tags`, not just the outermost context like [`logger::LogError`] does.
+#[cfg_attr(any(target_arch = "wasm32", debug_assertions), expect(unused_variables))]
+pub(crate) fn report_error(e: &anyhow::Error, tags: &[(&str, &dyn std::fmt::Display)]) {
+	#[cfg(all(not(target_arch = "wasm32"), not(debug_assertions)))]
+	sentry::with_scope(
+		|scope| for (key, value) in tags { scope.set_tag(key, value); },
+		|| sentry::capture_message(&format!("{e:#}"), sentry::Level::Error),
+	);
+
+	log::error!("{e:?}");
 }
 
 #[must_use]
 pub fn default<T: Default>() -> T { T::default() }
 
+/// The message of a caught panic payload, for the `&str` and `String` payloads `panic!` produces.
+pub(crate) fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+	panic.downcast_ref::<&str>().copied()
+		.or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+		.unwrap_or("<non-string panic payload>")
+}
+
 #[macro_export]
 macro_rules! spawn_complain {
 	($body: block) => { spawn_complain(async move { $body; Ok(()) }) };
@@ -116,6 +288,23 @@ macro_rules! hash {
 	}};
 }
 
+/// Asserts a record was logged on this thread while a [`logger::capture`] is alive.
+///
+/// `assert_logged!(log::Level::Error, contains "timeout")` or `assert_logged!(log::Level::Info, "exact message")`
+#[macro_export]
+macro_rules! assert_logged {
+	($level:expr, contains $needle:expr) => {{
+		let (level, needle) = ($level, $needle);
+		assert!($crate::logger::logged(level, |record| record.message.contains(needle)),
+			"no {level} record containing {needle:?} was logged, captured: {:#?}", $crate::logger::captured());
+	}};
+	($level:expr, $message:expr) => {{
+		let (level, message) = ($level, $message);
+		assert!($crate::logger::logged(level, |record| record.message == message),
+			"no {level} record {message:?} was logged, captured: {:#?}", $crate::logger::captured());
+	}};
+}
+
 #[extend::ext(pub, name = VerboseErrorForStatus)]
 impl reqwest::Response {
 	/// Basically
@@ -192,6 +381,99 @@ impl reqwest::Response {
 	}
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn spawn_complain_panic() {
+	let _capture = logger::capture();
+	let line = line!() + 1;
+	spawn_complain_send(async { if true { panic!("oh no") } Ok(()) });
+	tokio::task::yield_now().await;
+	let expected = format!("task spawned at {}:{line}:", file!());
+	assert_logged!(log::Level::Error, contains expected.as_str());
+	assert_logged!(log::Level::Error, contains "panicked: oh no");
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn spawn_complain_error_site() {
+	let _capture = logger::capture();
+	let line = line!() + 1;
+	spawn_complain_send_named("sync", async { anyhow::ensure!(1 + 1 == 3, "bad math"); Ok(()) });
+	tokio::task::yield_now().await;
+	let expected = format!("task sync spawned at {}:{line}:", file!());
+	assert_logged!(log::Level::Error, contains expected.as_str());
+	assert_logged!(log::Level::Error, contains "bad math");
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn spawn_complain_until_cancelled() {
+	let parent = CancellationToken::new();
+	let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+	spawn_complain_send_until(parent.child_token(), async move { let _tx = tx; future::pending::<anyhow::Result<()>>().await });
+
+	let child = parent.child_token();
+	let signalled = tokio::spawn(async move {
+		tokio::select! {
+			() = child.cancelled() => true,
+			() = future::pending() => false,
+		}
+	});
+
+	parent.cancel();
+	assert!(rx.await.is_err());
+	assert!(signalled.await.unwrap());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn abort_on_drop() {
+	assert_eq!(tokio::spawn(async { 5 }).abort_on_drop().await.unwrap(), 5);
+
+	let (tx, rx) = tokio::sync::oneshot::channel();
+	drop(tokio::spawn(async move { tx.send(()) }).abort_on_drop());
+	assert!(rx.await.is_err());
+
+	let (tx, rx) = tokio::sync::oneshot::channel();
+	drop(tokio::spawn(async move { tx.send(()) }).abort_on_drop().detach());
+	assert!(rx.await.is_ok());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn abort_on_cancel() {
+	let parent = CancellationToken::new();
+	let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+	let task = tokio::spawn(async move { let _tx = tx; future::pending::<()>().await }).abort_on_cancel(parent.child_token());
+	parent.cancel();
+	assert!(rx.await.is_err());
+	assert!(task.await.unwrap_err().is_cancelled());
+
+	let token = CancellationToken::new();
+	assert_eq!(tokio::spawn(async { 5 }).abort_on_cancel(token.clone()).await.unwrap(), 5);
+	let (tx, rx) = tokio::sync::oneshot::channel();
+	let task = tokio::spawn(async move { tx.send(()) }).abort_on_cancel(token.clone()).detach();
+	token.cancel();
+	assert!(rx.await.is_ok());
+	assert!(task.await.is_ok());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn abort_on_drop_set() {
+	let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+	let mut set = [
+		tokio::spawn(async move { let _tx = tx; future::pending::<i32>().await }).abort_on_drop(),
+		tokio::spawn(async { 1 }).abort_on_drop(),
+	].into_iter().collect::<AbortOnDropSet<_>>();
+	assert_eq!(set.len(), 2);
+
+	assert_eq!(set.join_next().await.unwrap().unwrap(), 1);
+	assert_eq!(set.len(), 1);
+	drop(set);
+	assert!(rx.await.is_err());
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::test]
 async fn try_json() {
@@ -0,0 +1,255 @@
+//! Words for durations and relative times, see [`Locale`]. Bundles [`English`], [`German`] and [`Russian`].
+
+/// CLDR plural categories, <https://cldr.unicode.org/index/cldr-spec/plural-rules>.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Plural {
+	Zero,
+	One,
+	Two,
+	Few,
+	Many,
+	Other,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+	Year,
+	Month,
+	Week,
+	Day,
+	Hour,
+	Minute,
+	Second,
+	Millisecond,
+	Microsecond,
+	Nanosecond,
+}
+
+impl TimeUnit {
+	/// Largest first.
+	pub const ALL: [Self; 10] = [
+		Self::Year,
+		Self::Month,
+		Self::Week,
+		Self::Day,
+		Self::Hour,
+		Self::Minute,
+		Self::Second,
+		Self::Millisecond,
+		Self::Microsecond,
+		Self::Nanosecond,
+	];
+
+	/// Length in nanoseconds, months and years are the naive 30 and 365 days.
+	/// Everything that converts between units goes through this.
+	pub const fn nanos(self) -> i128 {
+		const SECOND: i128 = 1_000_000_000;
+		const DAY: i128 = 86_400 * SECOND;
+		match self {
+			Self::Year => 365 * DAY,
+			Self::Month => 30 * DAY,
+			Self::Week => 7 * DAY,
+			Self::Day => DAY,
+			Self::Hour => 3600 * SECOND,
+			Self::Minute => 60 * SECOND,
+			Self::Second => SECOND,
+			Self::Millisecond => 1_000_000,
+			Self::Microsecond => 1_000,
+			Self::Nanosecond => 1,
+		}
+	}
+}
+
+/// Where a unit word goes, languages with cases need different ones ("3 Tage", but "vor 3 Tagen").
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Form {
+	/// "3 days"
+	Plain,
+	/// "in 3 days"
+	Future,
+	/// "3 days ago"
+	Past,
+}
+
+/// A language for [`crate::human_time::RelativeFormat`] and `Duration::display_as_word`.
+/// Implement it to add one, the provided methods only need overriding for unusual word order.
+pub trait Locale: Sync {
+	fn plural(&self, n: u128) -> Plural;
+	fn unit(&self, unit: TimeUnit, plural: Plural, form: Form) -> &str;
+	/// Appended right after the number, so include a space if the language wants one.
+	fn short_unit(&self, unit: TimeUnit) -> &str;
+	/// Wraps a span of one or more units, "in {span}".
+	fn future(&self, span: &str) -> String;
+	/// "{span} ago"
+	fn past(&self, span: &str) -> String;
+	/// Instead of "in 0 seconds".
+	fn just_now(&self) -> &str;
+	/// What a zero or negative `Duration::display_as_word` says.
+	fn a_moment(&self) -> &str;
+
+	/// Between the whole and the fractional part of a number, "1.5" or "1,5".
+	fn decimal_separator(&self) -> &str { "." }
+
+	/// "3 hours", or "3h" if `short`.
+	fn amount(&self, n: u128, unit: TimeUnit, form: Form, short: bool) -> String {
+		if short { format!("{n}{}", self.short_unit(unit)) } else { format!("{n} {}", self.unit(unit, self.plural(n), form)) }
+	}
+}
+
+pub struct English;
+pub struct German;
+pub struct Russian;
+
+impl Locale for English {
+	fn plural(&self, n: u128) -> Plural { if n == 1 { Plural::One } else { Plural::Other } }
+
+	fn unit(&self, unit: TimeUnit, plural: Plural, _: Form) -> &str {
+		let [one, other] = match unit {
+			TimeUnit::Year => ["year", "years"],
+			TimeUnit::Month => ["month", "months"],
+			TimeUnit::Week => ["week", "weeks"],
+			TimeUnit::Day => ["day", "days"],
+			TimeUnit::Hour => ["hour", "hours"],
+			TimeUnit::Minute => ["minute", "minutes"],
+			TimeUnit::Second => ["second", "seconds"],
+			TimeUnit::Millisecond => ["millisecond", "milliseconds"],
+			TimeUnit::Microsecond => ["microsecond", "microseconds"],
+			TimeUnit::Nanosecond => ["nanosecond", "nanoseconds"],
+		};
+		if plural == Plural::One { one } else { other }
+	}
+
+	fn short_unit(&self, unit: TimeUnit) -> &str {
+		match unit {
+			TimeUnit::Year => "y",
+			TimeUnit::Month => "mo",
+			TimeUnit::Week => "w",
+			TimeUnit::Day => "d",
+			TimeUnit::Hour => "h",
+			TimeUnit::Minute => "m",
+			TimeUnit::Second => "s",
+			TimeUnit::Millisecond => "ms",
+			TimeUnit::Microsecond => "µs",
+			TimeUnit::Nanosecond => "ns",
+		}
+	}
+
+	fn future(&self, span: &str) -> String { format!("in {span}") }
+	fn past(&self, span: &str) -> String { format!("{span} ago") }
+	fn just_now(&self) -> &str { "just now" }
+	fn a_moment(&self) -> &str { "a moment" }
+}
+
+impl Locale for German {
+	fn plural(&self, n: u128) -> Plural { if n == 1 { Plural::One } else { Plural::Other } }
+
+	fn unit(&self, unit: TimeUnit, plural: Plural, form: Form) -> &str {
+		// nominative for plain amounts, dative after "in" and "vor"
+		let [one, other, other_dative] = match unit {
+			TimeUnit::Year => ["Jahr", "Jahre", "Jahren"],
+			TimeUnit::Month => ["Monat", "Monate", "Monaten"],
+			TimeUnit::Week => ["Woche", "Wochen", "Wochen"],
+			TimeUnit::Day => ["Tag", "Tage", "Tagen"],
+			TimeUnit::Hour => ["Stunde", "Stunden", "Stunden"],
+			TimeUnit::Minute => ["Minute", "Minuten", "Minuten"],
+			TimeUnit::Second => ["Sekunde", "Sekunden", "Sekunden"],
+			TimeUnit::Millisecond => ["Millisekunde", "Millisekunden", "Millisekunden"],
+			TimeUnit::Microsecond => ["Mikrosekunde", "Mikrosekunden", "Mikrosekunden"],
+			TimeUnit::Nanosecond => ["Nanosekunde", "Nanosekunden", "Nanosekunden"],
+		};
+		match (plural, form) {
+			(Plural::One, _) => one,
+			(_, Form::Plain) => other,
+			_ => other_dative,
+		}
+	}
+
+	fn short_unit(&self, unit: TimeUnit) -> &str {
+		match unit {
+			TimeUnit::Year => " J.",
+			TimeUnit::Month => " Mon.",
+			TimeUnit::Week => " Wo.",
+			TimeUnit::Day => " T.",
+			TimeUnit::Hour => " Std.",
+			TimeUnit::Minute => " Min.",
+			TimeUnit::Second => " Sek.",
+			TimeUnit::Millisecond => " ms",
+			TimeUnit::Microsecond => " µs",
+			TimeUnit::Nanosecond => " ns",
+		}
+	}
+
+	fn future(&self, span: &str) -> String { format!("in {span}") }
+	fn past(&self, span: &str) -> String { format!("vor {span}") }
+	fn just_now(&self) -> &str { "gerade eben" }
+	fn a_moment(&self) -> &str { "einen Moment" }
+	fn decimal_separator(&self) -> &str { "," }
+}
+
+impl Locale for Russian {
+	fn plural(&self, n: u128) -> Plural {
+		match (n % 10, n % 100) {
+			(1, 11) => Plural::Many,
+			(1, _) => Plural::One,
+			(2..=4, 12..=14) => Plural::Many,
+			(2..=4, _) => Plural::Few,
+			_ => Plural::Many,
+		}
+	}
+
+	fn unit(&self, unit: TimeUnit, plural: Plural, form: Form) -> &str {
+		// the feminine ones take the accusative after "через" and before "назад"
+		let [one, one_accusative, few, many] = match unit {
+			TimeUnit::Year => ["год", "год", "года", "лет"],
+			TimeUnit::Month => ["месяц", "месяц", "месяца", "месяцев"],
+			TimeUnit::Week => ["неделя", "неделю", "недели", "недель"],
+			TimeUnit::Day => ["день", "день", "дня", "дней"],
+			TimeUnit::Hour => ["час", "час", "часа", "часов"],
+			TimeUnit::Minute => ["минута", "минуту", "минуты", "минут"],
+			TimeUnit::Second => ["секунда", "секунду", "секунды", "секунд"],
+			TimeUnit::Millisecond => ["миллисекунда", "миллисекунду", "миллисекунды", "миллисекунд"],
+			TimeUnit::Microsecond => ["микросекунда", "микросекунду", "микросекунды", "микросекунд"],
+			TimeUnit::Nanosecond => ["наносекунда", "наносекунду", "наносекунды", "наносекунд"],
+		};
+		match (plural, form) {
+			(Plural::One, Form::Plain) => one,
+			(Plural::One, _) => one_accusative,
+			// `other` is for fractions, "1,5 часа"
+			(Plural::Few | Plural::Other, _) => few,
+			_ => many,
+		}
+	}
+
+	fn short_unit(&self, unit: TimeUnit) -> &str {
+		match unit {
+			TimeUnit::Year => " г.",
+			TimeUnit::Month => " мес.",
+			TimeUnit::Week => " нед.",
+			TimeUnit::Day => " д.",
+			TimeUnit::Hour => " ч",
+			TimeUnit::Minute => " мин",
+			TimeUnit::Second => " с",
+			TimeUnit::Millisecond => " мс",
+			TimeUnit::Microsecond => " мкс",
+			TimeUnit::Nanosecond => " нс",
+		}
+	}
+
+	fn future(&self, span: &str) -> String { format!("через {span}") }
+	fn past(&self, span: &str) -> String { format!("{span} назад") }
+	fn just_now(&self) -> &str { "только что" }
+	fn a_moment(&self) -> &str { "мгновение" }
+	fn decimal_separator(&self) -> &str { "," }
+}
+
+#[test]
+fn plurals() {
+	let words = |locale: &dyn Locale, form| [1, 2, 5, 11, 21, 22, 112].map(|n| locale.amount(n, TimeUnit::Minute, form, false));
+
+	assert_eq!(words(&English, Form::Plain), ["1 minute", "2 minutes", "5 minutes", "11 minutes", "21 minutes", "22 minutes", "112 minutes"]);
+	assert_eq!(words(&Russian, Form::Plain), ["1 минута", "2 минуты", "5 минут", "11 минут", "21 минута", "22 минуты", "112 минут"]);
+	assert_eq!(words(&Russian, Form::Past)[..2], ["1 минуту", "2 минуты"]);
+	assert_eq!(German.amount(3, TimeUnit::Day, Form::Plain, false), "3 Tage");
+	assert_eq!(German.amount(3, TimeUnit::Day, Form::Future, false), "3 Tagen");
+	assert_eq!(German.amount(3, TimeUnit::Hour, Form::Plain, true), "3 Std.");
+}
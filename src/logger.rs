@@ -1,9 +1,22 @@
+use std::{cell::{Cell, RefCell}, sync::{Arc, Mutex, OnceLock, RwLock}};
+
 // log levels should be configured via RUST_LOG env var
 // smth like `RUST_LOG=info,my_crate=trace` where `info` is the level for all targets
 // and `my_crate=trace` is the level for just `my_crate`
 // and we can also do submodules like `RUST_LOG=trace,my_crate::foo=info`
-pub fn setup() {
-	env_logger::Builder::from_env(env_logger::Env::default())
+//
+// on wasm32 there is no env, so the same string is read from the `RUST_LOG` url param
+// (e.g. `?RUST_LOG=debug,my_crate=trace`) or, failing that, the `RUST_LOG` localStorage key
+//
+// the returned handle can swap the filter later without a restart, see `LogHandle::set_filter`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn setup() -> LogHandle {
+	// filtering is done by `Filtered` so it can be swapped at runtime, env_logger itself lets everything through
+	let mut output = env_logger::Builder::new();
+	output.filter_level(log::LevelFilter::Trace);
+	if let Ok(style) = std::env::var("RUST_LOG_STYLE") { output.parse_write_style(&style); }
+
+	output
 		.format(|buf, record| {
 			use std::io::Write;
 
@@ -21,13 +34,7 @@ pub fn setup() {
 				}));
 			let level_style_reset = level_style.render_reset();
 
-			let level = match record.level() {
-				log::Level::Trace => "TRACE",
-				log::Level::Debug => "DEBUG",
-				log::Level::Info  => "INFO ",
-				log::Level::Warn  => "WARN ",
-				log::Level::Error => "ERROR",
-			};
+			let level = level_label(record.level());
 
 			let magenta = anstyle::Style::new()
 				.fg_color(Some(anstyle::Color::Ansi(anstyle::AnsiColor::Magenta)));
@@ -40,8 +47,122 @@ pub fn setup() {
 				line = record.line().unwrap_or(0),
 				args = record.args(),
 			)
-		})
-		.init();
+		});
+
+	install(output.build(), env_filter::Builder::from_env("RUST_LOG").build())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn setup() -> LogHandle {
+	let mut filter = env_filter::Builder::new();
+	if let Some(directives) = wasm_directives() { filter.parse(&directives); }
+
+	install(ConsoleLogger, filter.build())
+}
+
+/// Swaps the filter of the logger installed by [`setup`] at runtime,
+/// e.g. to turn on trace for one module from an admin endpoint and back off again.
+#[derive(Clone)]
+pub struct LogHandle(Arc<RwLock<env_filter::Filter>>);
+
+impl LogHandle {
+	/// Takes the same directives as `RUST_LOG`, e.g. `info,my_crate::db=trace`.
+	/// On error the current filter is kept.
+	pub fn set_filter(&self, directives: &str) -> Result<(), env_filter::ParseError> {
+		let filter = env_filter::Builder::new().try_parse(directives)?.build();
+		let active = ACTIVE_CAPTURES.lock().unwrap();
+		log::set_max_level(max_level(*active, Some(&filter)));
+		*self.0.write().unwrap() = filter;
+		Ok(())
+	}
+}
+
+fn install(output: impl log::Log + 'static, filter: env_filter::Filter) -> LogHandle {
+	let handle = LogHandle(Arc::new(RwLock::new(filter)));
+	let _ = FILTER.set(handle.0.clone());
+	log::set_max_level(max_level(*ACTIVE_CAPTURES.lock().unwrap(), Some(&handle.0.read().unwrap())));
+	log::set_boxed_logger(Box::new(Filtered { filter: handle.0.clone(), output })).expect("logger::setup should only be called once");
+	handle
+}
+
+struct Filtered<L> {
+	filter: Arc<RwLock<env_filter::Filter>>,
+	output: L,
+}
+
+impl<L: log::Log> log::Log for Filtered<L> {
+	fn enabled(&self, metadata: &log::Metadata<'_>) -> bool { is_capturing() || self.filter.read().unwrap().enabled(metadata) }
+
+	fn log(&self, record: &log::Record<'_>) {
+		capture_record(record);
+		if self.filter.read().unwrap().matches(record) { self.output.log(record); }
+	}
+
+	fn flush(&self) { self.output.flush(); }
+}
+
+fn level_label(level: log::Level) -> &'static str {
+	match level {
+		log::Level::Trace => "TRACE",
+		log::Level::Debug => "DEBUG",
+		log::Level::Info  => "INFO ",
+		log::Level::Warn  => "WARN ",
+		log::Level::Error => "ERROR",
+	}
+}
+
+#[cfg(target_arch = "wasm32")]
+fn wasm_directives() -> Option<String> {
+	let window = web_sys::window()?;
+	let from_url = window.location().search().ok()
+		.and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())
+		.and_then(|params| params.get("RUST_LOG"));
+	from_url.or_else(|| window.local_storage().ok().flatten()?.get_item("RUST_LOG").ok().flatten())
+}
+
+/// Browser console counterpart of the env_logger format above,
+/// same layout with the ansi palette translated to css.
+#[cfg(target_arch = "wasm32")]
+struct ConsoleLogger;
+
+#[cfg(target_arch = "wasm32")]
+impl log::Log for ConsoleLogger {
+	fn enabled(&self, _: &log::Metadata<'_>) -> bool { true }
+
+	fn log(&self, record: &log::Record<'_>) {
+		let dimmed = "color: rgb(126, 126, 126)";
+		let level_style = match record.level() {
+			log::Level::Trace => "color: rgb(169, 169, 169)",
+			log::Level::Debug => "color: #11a8cd",
+			log::Level::Info  => "color: #0dbc79",
+			log::Level::Warn  => "color: #e5e510",
+			log::Level::Error => "color: #cd3131",
+		};
+		let magenta = "color: #bc3fbc";
+
+		// console.* reads %c, %s, %o and %d as substitutions, so the text we don't control gets them escaped
+		let escape = |text: &str| text.replace('%', "%%");
+		let line = format!("[%c{time} %c{level} %c{module}%c] {args} %c@ {file}:{line}",
+			time = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+			level = level_label(record.level()),
+			module = escape(record.module_path().unwrap_or("module?")),
+			file = escape(record.file().unwrap_or("file?")),
+			line = record.line().unwrap_or(0),
+			args = escape(&record.args().to_string()),
+		);
+		let data = [line.as_str(), dimmed, level_style, magenta, "", dimmed].into_iter()
+			.map(wasm_bindgen::JsValue::from_str)
+			.collect::<web_sys::js_sys::Array>();
+
+		match record.level() {
+			log::Level::Error => web_sys::console::error(&data),
+			log::Level::Warn => web_sys::console::warn(&data),
+			log::Level::Info => web_sys::console::info(&data),
+			log::Level::Debug | log::Level::Trace => web_sys::console::debug(&data),
+		}
+	}
+
+	fn flush(&self) {}
 }
 
 
@@ -62,3 +183,176 @@ impl<T> anyhow::Result<T> {
 		}
 	}
 }
+
+thread_local! {
+	static CAPTURED: RefCell<Option<Vec<CapturedRecord>>> = const { RefCell::new(None) };
+	// nested captures share the buffer, it goes away with the outermost one
+	static CAPTURE_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// captures bypass the filter, so while any are alive everything has to reach the logger.
+// held while setting the max level too, or a capture starting on another thread could get its Trace overwritten
+static ACTIVE_CAPTURES: Mutex<usize> = Mutex::new(0);
+
+// the filter of the logger installed by `setup`, so the max level can be put back after the last capture
+static FILTER: OnceLock<Arc<RwLock<env_filter::Filter>>> = OnceLock::new();
+
+fn max_level(active_captures: usize, filter: Option<&env_filter::Filter>) -> log::LevelFilter {
+	if active_captures > 0 { log::LevelFilter::Trace } else { filter.map_or(log::LevelFilter::Off, env_filter::Filter::filter) }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRecord {
+	pub level: log::Level,
+	pub target: String,
+	pub message: String,
+	pub kv: Vec<(String, String)>,
+}
+
+/// Keeps every record logged on the current thread in memory until dropped, regardless of the filter.
+/// Meant for tests, check the records with [`crate::assert_logged!`] or [`captured`].
+///
+/// Installs a capture-only logger if [`setup`] hasn't been called,
+/// does nothing useful if some other logger is installed.
+#[must_use]
+pub fn capture() -> Capture {
+	let _ = log::set_boxed_logger(Box::new(CaptureOnly));
+	if CAPTURE_DEPTH.replace(CAPTURE_DEPTH.get() + 1) == 0 { CAPTURED.with_borrow_mut(|captured| *captured = Some(Vec::new())); }
+	let mut active = ACTIVE_CAPTURES.lock().unwrap();
+	*active += 1;
+	log::set_max_level(log::LevelFilter::Trace);
+	drop(active);
+	Capture(std::marker::PhantomData)
+}
+
+/// Stops capturing on drop. Not `Send`, the buffer belongs to the thread that created it.
+pub struct Capture(std::marker::PhantomData<*const ()>);
+
+impl Drop for Capture {
+	fn drop(&mut self) {
+		CAPTURE_DEPTH.set(CAPTURE_DEPTH.get() - 1);
+		if CAPTURE_DEPTH.get() == 0 { CAPTURED.with_borrow_mut(|captured| *captured = None); }
+		let mut active = ACTIVE_CAPTURES.lock().unwrap();
+		*active -= 1;
+		if *active == 0 {
+			log::set_max_level(match FILTER.get() {
+				Some(filter) => max_level(0, Some(&filter.read().unwrap())),
+				None => max_level(0, None),
+			});
+		}
+	}
+}
+
+/// Everything captured on this thread so far, empty if not capturing.
+pub fn captured() -> Vec<CapturedRecord> {
+	CAPTURED.with_borrow(|captured| captured.clone().unwrap_or_default())
+}
+
+pub fn clear_captured() {
+	CAPTURED.with_borrow_mut(|captured| if let Some(captured) = captured { captured.clear(); });
+}
+
+pub fn logged(level: log::Level, predicate: impl Fn(&CapturedRecord) -> bool) -> bool {
+	CAPTURED.with_borrow(|captured| captured.iter().flatten().any(|record| record.level == level && predicate(record)))
+}
+
+fn is_capturing() -> bool {
+	CAPTURED.with_borrow(Option::is_some)
+}
+
+fn capture_record(record: &log::Record<'_>) {
+	struct Collect<'a>(&'a mut Vec<(String, String)>);
+	impl<'kvs> log::kv::VisitSource<'kvs> for Collect<'_> {
+		fn visit_pair(&mut self, key: log::kv::Key<'kvs>, value: log::kv::Value<'kvs>) -> Result<(), log::kv::Error> {
+			self.0.push((key.to_string(), value.to_string()));
+			Ok(())
+		}
+	}
+
+	CAPTURED.with_borrow_mut(|captured| {
+		let Some(captured) = captured else { return };
+		let mut kv = Vec::new();
+		let _ = record.key_values().visit(&mut Collect(&mut kv));
+		captured.push(CapturedRecord {
+			level: record.level(),
+			target: record.target().to_owned(),
+			message: record.args().to_string(),
+			kv,
+		});
+	});
+}
+
+struct CaptureOnly;
+
+impl log::Log for CaptureOnly {
+	fn enabled(&self, _: &log::Metadata<'_>) -> bool { is_capturing() }
+	fn log(&self, record: &log::Record<'_>) { capture_record(record); }
+	fn flush(&self) {}
+}
+
+#[test]
+fn set_filter() {
+	let handle = LogHandle(Arc::new(RwLock::new(env_filter::Builder::new().build())));
+	assert_eq!(handle.0.read().unwrap().filter(), log::LevelFilter::Error);
+
+	handle.set_filter("info,my_crate::db=trace").unwrap();
+	assert_eq!(handle.0.read().unwrap().filter(), log::LevelFilter::Trace);
+
+	assert!(handle.set_filter("info,my_crate=loud").is_err());
+	assert_eq!(handle.0.read().unwrap().filter(), log::LevelFilter::Trace);
+}
+
+#[test]
+fn set_filter_swaps_output() {
+	#[derive(Clone, Default)]
+	struct Collect(Arc<Mutex<Vec<String>>>);
+	impl log::Log for Collect {
+		fn enabled(&self, _: &log::Metadata<'_>) -> bool { true }
+		fn log(&self, record: &log::Record<'_>) { self.0.lock().unwrap().push(record.args().to_string()); }
+		fn flush(&self) {}
+	}
+
+	let output = Collect::default();
+	let handle = LogHandle(Arc::new(RwLock::new(env_filter::Builder::new().parse("info").build())));
+	let logger = Filtered { filter: handle.0.clone(), output: output.clone() };
+	let log = |level, target, message| log::Log::log(&logger, &log::Record::builder().level(level).target(target).args(format_args!("{message}")).build());
+
+	log(log::Level::Debug, "my_crate::db", "dropped");
+	log(log::Level::Info, "my_crate::http", "kept");
+	handle.set_filter("warn,my_crate::db=debug").unwrap();
+	log(log::Level::Debug, "my_crate::db", "now kept");
+	log(log::Level::Info, "my_crate::http", "now dropped");
+
+	assert_eq!(*output.0.lock().unwrap(), ["kept", "now kept"]);
+}
+
+#[test]
+fn capture_log_error() {
+	let _capture = capture();
+
+	anyhow::Result::<()>::Err(anyhow::anyhow!("request timeout")).log_error();
+	log::debug!(user_id = 5; "looking up user");
+
+	crate::assert_logged!(log::Level::Error, contains "timeout");
+	crate::assert_logged!(log::Level::Debug, "looking up user");
+	assert!(!logged(log::Level::Warn, |_| true));
+	assert_eq!(captured()[1].kv, vec![("user_id".to_owned(), "5".to_owned())]);
+
+	clear_captured();
+	assert!(captured().is_empty());
+}
+
+#[test]
+fn nested_capture() {
+	let outer = capture();
+	log::info!("outer");
+	{
+		let _inner = capture();
+		log::info!("inner");
+	}
+	log::info!("after inner");
+	assert_eq!(captured().into_iter().map(|record| record.message).collect::<Vec<_>>(), ["outer", "inner", "after inner"]);
+
+	drop(outer);
+	assert!(!is_capturing());
+}
@@ -0,0 +1,201 @@
+use chrono::{Datelike as _, Months, NaiveDate, Weekday};
+
+/// A rule for repeating dates, e.g. billing days.
+///
+/// The anchor only fixes the phase (weekday, day of month, which month of the quarter),
+/// dates before it are occurrences too, same as [`crate::chrono_utils::ChronoNaiveDateExt::monthly_after`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Recurrence {
+	/// Every `interval` weeks on the anchor's weekday.
+	Weekly { anchor: NaiveDate, interval: u32 },
+	/// Every `interval` months on the anchor's day, clamped to the end of shorter months.
+	Monthly { anchor: NaiveDate, interval: u32 },
+	/// The last Monday to Friday of every month.
+	LastBusinessDay,
+	/// The `n`-th `weekday` of every month, negative `n` counts from the end (-1 is the last one).
+	/// Months that don't have one (the 5th Monday) are skipped.
+	NthWeekday { n: i8, weekday: Weekday },
+}
+
+impl Recurrence {
+	pub fn weekly(anchor: NaiveDate) -> Self { Self::Weekly { anchor, interval: 1 } }
+	pub fn biweekly(anchor: NaiveDate) -> Self { Self::Weekly { anchor, interval: 2 } }
+	pub fn monthly(anchor: NaiveDate) -> Self { Self::Monthly { anchor, interval: 1 } }
+	pub fn quarterly(anchor: NaiveDate) -> Self { Self::Monthly { anchor, interval: 3 } }
+	pub fn yearly(anchor: NaiveDate) -> Self { Self::Monthly { anchor, interval: 12 } }
+	pub fn last_business_day() -> Self { Self::LastBusinessDay }
+	pub fn nth_weekday(n: i8, weekday: Weekday) -> Self { Self::NthWeekday { n, weekday } }
+
+	/// Occurrences strictly after `date`, in order.
+	pub fn after(self, date: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+		std::iter::successors(self.next_after(date), move |&date| self.next_after(date))
+	}
+
+	/// The first occurrence strictly after `date`, `None` past the end of [`NaiveDate`]'s range
+	/// or if the rule can never happen (a zero interval, `n` of 0 or beyond ±5).
+	pub fn next_after(self, date: NaiveDate) -> Option<NaiveDate> {
+		match self {
+			Self::Weekly { anchor, interval } => {
+				let period = 7 * i64::from(interval);
+				if period == 0 { return None; }
+				let periods = (date - anchor).num_days().div_euclid(period) + 1;
+				anchor.checked_add_signed(chrono::Duration::try_days(periods * period)?)
+			},
+			Self::Monthly { anchor, interval } => {
+				if interval == 0 { return None; }
+				let months = month_index(date) - month_index(anchor);
+				let month = month_index(anchor) + months.div_euclid(interval.into()) * i64::from(interval);
+				let candidate = clamped(month, anchor.day())?;
+				if candidate > date { Some(candidate) } else { clamped(month + i64::from(interval), anchor.day()) }
+			},
+			Self::LastBusinessDay => {
+				let candidate = last_business_day(month_index(date))?;
+				if candidate > date { Some(candidate) } else { last_business_day(month_index(date) + 1) }
+			},
+			Self::NthWeekday { n, weekday } => {
+				if n == 0 || n.unsigned_abs() > 5 { return None; }
+				// every weekday comes up a 5th time at least once every four months
+				(month_index(date)..month_index(date) + 5)
+					.filter_map(|month| nth_weekday(month, n, weekday))
+					.find(|&candidate| candidate > date)
+			},
+		}
+	}
+
+	/// The last occurrence strictly before `date`, the mirror image of [`Recurrence::next_after`].
+	pub fn previous_before(self, date: NaiveDate) -> Option<NaiveDate> {
+		match self {
+			Self::Weekly { anchor, interval } => {
+				let period = 7 * i64::from(interval);
+				if period == 0 { return None; }
+				let periods = ((date - anchor).num_days() - 1).div_euclid(period);
+				anchor.checked_add_signed(chrono::Duration::try_days(periods * period)?)
+			},
+			Self::Monthly { anchor, interval } => {
+				if interval == 0 { return None; }
+				let months = month_index(date) - month_index(anchor);
+				let month = month_index(anchor) + months.div_euclid(interval.into()) * i64::from(interval);
+				let candidate = clamped(month, anchor.day())?;
+				if candidate < date { Some(candidate) } else { clamped(month - i64::from(interval), anchor.day()) }
+			},
+			Self::LastBusinessDay => {
+				let candidate = last_business_day(month_index(date))?;
+				if candidate < date { Some(candidate) } else { last_business_day(month_index(date) - 1) }
+			},
+			Self::NthWeekday { n, weekday } => {
+				if n == 0 || n.unsigned_abs() > 5 { return None; }
+				(month_index(date) - 4..=month_index(date)).rev()
+					.filter_map(|month| nth_weekday(month, n, weekday))
+					.find(|&candidate| candidate < date)
+			},
+		}
+	}
+}
+
+pub(crate) fn month_index(date: NaiveDate) -> i64 { i64::from(date.year()) * 12 + i64::from(date.month0()) }
+
+pub(crate) fn first_of_month(month: i64) -> Option<NaiveDate> {
+	NaiveDate::from_ymd_opt(month.div_euclid(12).try_into().ok()?, u32::try_from(month.rem_euclid(12)).ok()? + 1, 1)
+}
+
+pub(crate) fn last_of_month(month: i64) -> Option<NaiveDate> {
+	first_of_month(month)?.checked_add_months(Months::new(1))?.pred_opt()
+}
+
+/// `day` of `month`, or its last day if it's shorter.
+fn clamped(month: i64, day: u32) -> Option<NaiveDate> {
+	let last = last_of_month(month)?;
+	last.with_day(day.min(last.day()))
+}
+
+fn last_business_day(month: i64) -> Option<NaiveDate> {
+	let last = last_of_month(month)?;
+	let back = match last.weekday() { Weekday::Sat => 1, Weekday::Sun => 2, _ => 0 };
+	last.checked_sub_days(chrono::Days::new(back))
+}
+
+fn nth_weekday(month: i64, n: i8, weekday: Weekday) -> Option<NaiveDate> {
+	let first = first_of_month(month)?;
+	if n > 0 { return NaiveDate::from_weekday_of_month_opt(first.year(), first.month(), weekday, n.unsigned_abs()); }
+
+	let last = last_of_month(month)?;
+	let back = (last.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+	let date = last.checked_sub_days(chrono::Days::new(u64::from(back) + 7 * (u64::from(n.unsigned_abs()) - 1)))?;
+	(date.month() == first.month()).then_some(date)
+}
+
+#[test]
+fn monthly() {
+	use crate::chrono_utils::date;
+
+	// same cases as the monthly_after tests
+	for (a, b, c) in [
+		(date(2023, 5, 5), date(2024, 1, 1), date(2024, 1, 5)),
+		(date(2023, 5, 1), date(2024, 1, 5), date(2024, 2, 1)),
+		(date(2023, 8, 31), date(2024, 2, 1), date(2024, 2, 29)),
+		(date(2022, 8, 31), date(2023, 2, 1), date(2023, 2, 28)),
+		(date(2023, 8, 31), date(2024, 8, 31), date(2024, 9, 30)),
+		(date(2023, 9, 1), date(2024, 9, 1), date(2024, 10, 1)),
+		(date(2023, 9, 1), date(2024, 8, 31), date(2024, 9, 1)),
+		(date(2023, 9, 15), date(2024, 12, 15), date(2025, 1, 15)),
+	] {
+		assert_eq!(Recurrence::monthly(a).after(b).next(), Some(c), "monthly({a}) after {b}");
+	}
+
+	let eom = Recurrence::monthly(date(2023, 8, 31)).after(date(2024, 1, 31)).take(3).collect::<Vec<_>>();
+	assert_eq!(eom, vec![date(2024, 2, 29), date(2024, 3, 31), date(2024, 4, 30)]);
+
+	let quarterly = Recurrence::quarterly(date(2023, 11, 30)).after(date(2024, 1, 1)).take(3).collect::<Vec<_>>();
+	assert_eq!(quarterly, vec![date(2024, 2, 29), date(2024, 5, 30), date(2024, 8, 30)]);
+
+	let yearly = Recurrence::yearly(date(2020, 2, 29)).after(date(2020, 2, 29)).take(4).collect::<Vec<_>>();
+	assert_eq!(yearly, vec![date(2021, 2, 28), date(2022, 2, 28), date(2023, 2, 28), date(2024, 2, 29)]);
+
+	assert_eq!(Recurrence::Monthly { anchor: date(2024, 1, 1), interval: 0 }.next_after(date(2024, 1, 1)), None);
+}
+
+#[test]
+fn weekly_and_weekdays() {
+	use crate::chrono_utils::date;
+
+	assert_eq!(date(2024, 1, 1).weekday(), Weekday::Mon);
+	assert_eq!(Recurrence::weekly(date(2024, 1, 1)).next_after(date(2024, 1, 1)), Some(date(2024, 1, 8)));
+	assert_eq!(Recurrence::weekly(date(2024, 1, 1)).next_after(date(2023, 12, 27)), Some(date(2024, 1, 1)));
+	let biweekly = Recurrence::biweekly(date(2024, 1, 1)).after(date(2024, 1, 3)).take(2).collect::<Vec<_>>();
+	assert_eq!(biweekly, vec![date(2024, 1, 15), date(2024, 1, 29)]);
+	assert_eq!(Recurrence::biweekly(date(2024, 1, 15)).next_after(date(2023, 12, 31)), Some(date(2024, 1, 1)));
+
+	// march 2024 ends on a sunday, august on a saturday
+	let last_business_day = Recurrence::last_business_day().after(date(2024, 2, 29)).take(2).collect::<Vec<_>>();
+	assert_eq!(last_business_day, vec![date(2024, 3, 29), date(2024, 4, 30)]);
+	assert_eq!(Recurrence::last_business_day().next_after(date(2024, 8, 15)), Some(date(2024, 8, 30)));
+
+	let second_tuesday = Recurrence::nth_weekday(2, Weekday::Tue).after(date(2024, 1, 9)).take(2).collect::<Vec<_>>();
+	assert_eq!(second_tuesday, vec![date(2024, 2, 13), date(2024, 3, 12)]);
+	assert_eq!(Recurrence::nth_weekday(-1, Weekday::Fri).next_after(date(2024, 5, 1)), Some(date(2024, 5, 31)));
+	assert_eq!(Recurrence::nth_weekday(-2, Weekday::Fri).next_after(date(2024, 5, 24)), Some(date(2024, 6, 21)));
+	// the next month with five mondays after february 2024 is april
+	assert_eq!(Recurrence::nth_weekday(5, Weekday::Mon).next_after(date(2024, 2, 1)), Some(date(2024, 4, 29)));
+	assert_eq!(Recurrence::nth_weekday(6, Weekday::Mon).next_after(date(2024, 2, 1)), None);
+	assert_eq!(Recurrence::nth_weekday(i8::MIN, Weekday::Mon).next_after(date(2024, 2, 1)), None);
+	assert_eq!(Recurrence::nth_weekday(i8::MIN, Weekday::Mon).previous_before(date(2024, 2, 1)), None);
+}
+
+#[test]
+fn previous_before() {
+	use crate::chrono_utils::date;
+
+	assert_eq!(Recurrence::monthly(date(2023, 8, 31)).previous_before(date(2024, 3, 1)), Some(date(2024, 2, 29)));
+	assert_eq!(Recurrence::monthly(date(2023, 8, 31)).previous_before(date(2024, 2, 29)), Some(date(2024, 1, 31)));
+	assert_eq!(Recurrence::quarterly(date(2023, 11, 30)).previous_before(date(2024, 5, 31)), Some(date(2024, 5, 30)));
+	assert_eq!(Recurrence::biweekly(date(2024, 1, 1)).previous_before(date(2024, 1, 15)), Some(date(2024, 1, 1)));
+	assert_eq!(Recurrence::biweekly(date(2024, 1, 1)).previous_before(date(2024, 1, 16)), Some(date(2024, 1, 15)));
+	assert_eq!(Recurrence::last_business_day().previous_before(date(2024, 4, 30)), Some(date(2024, 3, 29)));
+	assert_eq!(Recurrence::nth_weekday(5, Weekday::Mon).previous_before(date(2024, 4, 1)), Some(date(2024, 1, 29)));
+
+	// every occurrence is the one before the next
+	let rule = Recurrence::nth_weekday(-2, Weekday::Thu);
+	for occurrence in rule.after(date(2023, 1, 1)).take(30) {
+		assert_eq!(rule.previous_before(rule.next_after(occurrence).unwrap()), Some(occurrence));
+	}
+}
@@ -0,0 +1,556 @@
+//! iCalendar (RFC 5545) recurrence rules like `FREQ=MONTHLY;BYDAY=-1FR;COUNT=6`.
+//!
+//! Covers FREQ (daily to yearly), INTERVAL, COUNT, UNTIL, BYDAY, BYMONTHDAY, BYMONTH, BYSETPOS and WKST,
+//! plus DTSTART/EXDATE through [`RecurrenceSet`].
+
+use std::{fmt, str::FromStr};
+use chrono::{Datelike as _, NaiveDate, NaiveDateTime, NaiveTime, TimeZone as _, Weekday};
+use itertools::Itertools as _;
+use crate::{chrono_utils::DstPolicy, recurrence::{first_of_month, last_of_month, month_index}};
+
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+	#[error("{0} is missing")]
+	Missing(&'static str),
+	#[error("expected NAME=VALUE, got {0:?}")]
+	Malformed(String),
+	#[error("invalid {part} {value:?}")]
+	Invalid { part: String, value: String },
+	#[error("{0} is not supported")]
+	Unsupported(String),
+	#[error("COUNT and UNTIL can't both be set")]
+	CountAndUntil,
+	#[error("BYDAY with a position only works with FREQ=MONTHLY or FREQ=YEARLY")]
+	PositionalByDay,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Frequency {
+	#[default]
+	Daily,
+	Weekly,
+	Monthly,
+	Yearly,
+}
+
+/// An `UNTIL`, `DTSTART` or `EXDATE` value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateOrTime {
+	/// `20240131`
+	Date(NaiveDate),
+	/// `20240131T090000`, floating or in the `TZID` of the property.
+	Local(NaiveDateTime),
+	/// `20240131T090000Z`
+	Utc(NaiveDateTime),
+}
+
+impl DateOrTime {
+	pub fn naive(self) -> NaiveDateTime {
+		match self {
+			Self::Date(date) => date.and_time(NaiveTime::MIN),
+			Self::Local(time) | Self::Utc(time) => time,
+		}
+	}
+
+	/// Whether local time `time` in `tz` falls on this date or time.
+	fn matches(self, time: NaiveDateTime, tz: chrono_tz::Tz) -> bool {
+		match self {
+			Self::Date(date) => time.date() == date,
+			Self::Local(local) => time == local,
+			Self::Utc(utc) => tz.from_utc_datetime(&utc).naive_local() == time,
+		}
+	}
+
+	/// Whether local time `time` in `tz` is this or earlier.
+	fn not_before(self, time: NaiveDateTime, tz: chrono_tz::Tz) -> bool {
+		match self {
+			Self::Date(date) => time.date() <= date,
+			Self::Local(local) => time <= local,
+			Self::Utc(utc) => time <= tz.from_utc_datetime(&utc).naive_local(),
+		}
+	}
+}
+
+impl FromStr for DateOrTime {
+	type Err = chrono::ParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Some(utc) = s.strip_suffix('Z') { return Ok(Self::Utc(NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")?)); }
+		if s.contains('T') { return Ok(Self::Local(NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S")?)); }
+		Ok(Self::Date(NaiveDate::parse_from_str(s, "%Y%m%d")?))
+	}
+}
+
+impl fmt::Display for DateOrTime {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Date(date) => write!(f, "{}", date.format("%Y%m%d")),
+			Self::Local(time) => write!(f, "{}", time.format("%Y%m%dT%H%M%S")),
+			Self::Utc(time) => write!(f, "{}Z", time.format("%Y%m%dT%H%M%S")),
+		}
+	}
+}
+
+/// `MO`, or `-1FR` for the last friday of the month (or year).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ByDay {
+	pub n: Option<i8>,
+	pub weekday: Weekday,
+}
+
+impl fmt::Display for ByDay {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(n) = self.n { write!(f, "{n}")?; }
+		f.write_str(weekday_code(self.weekday))
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, smart_default::SmartDefault)]
+pub struct RRule {
+	pub freq: Frequency,
+	#[default(1)]
+	pub interval: u32,
+	pub count: Option<u32>,
+	/// Inclusive.
+	pub until: Option<DateOrTime>,
+	pub by_day: Vec<ByDay>,
+	/// Negative days count from the end of the month.
+	pub by_month_day: Vec<i8>,
+	pub by_month: Vec<u32>,
+	/// Picks from each period's occurrences, negative positions count from the end.
+	pub by_set_pos: Vec<i16>,
+	#[default(Weekday::Mon)]
+	pub week_start: Weekday,
+}
+
+impl RRule {
+	/// Occurrences from `dtstart` on, floating (no timezone). `dtstart` is always the first one and counts towards `COUNT`,
+	/// even if it doesn't match the rule (RFC 5545 3.8.5.3).
+	/// Ends after `COUNT` or `UNTIL`, or when the rule can't produce anything anymore (`BYMONTHDAY=30;BYMONTH=2`).
+	pub fn iter(&self, dtstart: NaiveDateTime) -> Occurrences<'_> { self.occurrences(dtstart, true) }
+
+	/// Days matching the rule from `dtstart` on. Unlike [`RRule::iter`], `dtstart` is only the first one if it matches the rule,
+	/// so this answers "the last friday of each month starting in January" rather than what a calendar would show.
+	pub fn dates(&self, dtstart: NaiveDate) -> impl Iterator<Item = NaiveDate> + '_ {
+		self.occurrences(dtstart.and_time(NaiveTime::MIN), false).map(|time| time.date())
+	}
+
+	fn occurrences(&self, dtstart: NaiveDateTime, with_dtstart: bool) -> Occurrences<'_> {
+		Occurrences {
+			rule: self,
+			dtstart,
+			with_dtstart,
+			until: self.until.map(|until| (until, chrono_tz::UTC)),
+			period: 0,
+			pending: Vec::new().into_iter(),
+			emitted: 0,
+			done: false,
+		}
+	}
+
+	/// How many periods in a row can come up empty before none ever will again.
+	/// The gregorian calendar repeats every 400 years, so that's the periods it takes to get back to the same spot in the cycle.
+	fn max_empty_periods(&self) -> u32 {
+		let cycle: u32 = match self.freq {
+			Frequency::Daily => 146_097,
+			Frequency::Weekly => 20_871,
+			Frequency::Monthly => 4_800,
+			Frequency::Yearly => 400,
+		};
+		let (mut a, mut b) = (cycle, self.interval.max(1));
+		while b != 0 { (a, b) = (b, a % b); }
+		cycle / a
+	}
+
+	/// Candidate days of period number `period` counted from `dtstart`'s, in order. `None` past the end of chrono's range.
+	fn period(&self, dtstart: NaiveDate, period: i64) -> Option<Vec<NaiveDate>> {
+		let step = period.checked_mul(self.interval.into())?;
+		let days = match self.freq {
+			Frequency::Daily => vec![dtstart.checked_add_signed(chrono::Duration::try_days(step)?)?],
+			Frequency::Weekly => {
+				let back = (7 + dtstart.weekday().num_days_from_monday() - self.week_start.num_days_from_monday()) % 7;
+				let start = dtstart.checked_sub_days(chrono::Days::new(back.into()))?.checked_add_signed(chrono::Duration::try_weeks(step)?)?;
+				start.iter_days().take(7).collect()
+			},
+			Frequency::Monthly => {
+				let month = month_index(dtstart).checked_add(step)?;
+				let first = first_of_month(month)?;
+				first.iter_days().take_while(|day| day.month() == first.month()).collect()
+			},
+			Frequency::Yearly => {
+				let year = i32::try_from(i64::from(dtstart.year()).checked_add(step)?).ok()?;
+				NaiveDate::from_ymd_opt(year, 1, 1)?.iter_days().take_while(|day| day.year() == year).collect()
+			},
+		};
+		let days = days.into_iter().filter(|&day| self.matches(dtstart, day)).collect::<Vec<_>>();
+
+		if self.by_set_pos.is_empty() { return Some(days); }
+		let len = i64::try_from(days.len()).ok()?;
+		Some(self.by_set_pos.iter()
+			.filter_map(|&pos| days.get(usize::try_from(if pos > 0 { i64::from(pos) - 1 } else { len + i64::from(pos) }).ok()?).copied())
+			.sorted()
+			.dedup()
+			.collect())
+	}
+
+	fn matches(&self, dtstart: NaiveDate, day: NaiveDate) -> bool {
+		let expands_days = !self.by_day.is_empty() || !self.by_month_day.is_empty();
+
+		let month_ok = if self.by_month.is_empty() {
+			self.freq != Frequency::Yearly || expands_days || day.month() == dtstart.month()
+		} else {
+			self.by_month.contains(&day.month())
+		};
+
+		let days_in_month = last_of_month(month_index(day)).map_or(31, |last| last.day());
+		let month_day_ok = if self.by_month_day.is_empty() {
+			!matches!(self.freq, Frequency::Monthly | Frequency::Yearly) || !self.by_day.is_empty() || day.day() == dtstart.day()
+		} else {
+			self.by_month_day.iter().any(|&n| i64::from(n) == i64::from(day.day()) || i64::from(n) == i64::from(day.day()) - i64::from(days_in_month) - 1)
+		};
+
+		let day_ok = if self.by_day.is_empty() {
+			self.freq != Frequency::Weekly || day.weekday() == dtstart.weekday()
+		} else {
+			// positions count within the month, unless it's a yearly rule not narrowed down to months
+			let (index, len) = if self.freq == Frequency::Yearly && self.by_month.is_empty() {
+				(day.ordinal0(), NaiveDate::from_ymd_opt(day.year(), 12, 31).map_or(366, |last| last.ordinal()))
+			} else {
+				(day.day0(), days_in_month)
+			};
+			self.by_day.iter().any(|by_day| {
+				by_day.weekday == day.weekday() && by_day.n.is_none_or(|n| {
+					let n = i64::from(n);
+					if n > 0 { n == i64::from(index / 7) + 1 } else { -n == i64::from((len - 1 - index) / 7) + 1 }
+				})
+			})
+		};
+
+		month_ok && month_day_ok && day_ok
+	}
+}
+
+impl FromStr for RRule {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		let s = s.strip_prefix("RRULE:").unwrap_or(s);
+		let mut rule = Self::default();
+		let mut freq = None;
+
+		for part in s.split(';').filter(|part| !part.is_empty()) {
+			let (name, value) = part.split_once('=').ok_or_else(|| ParseError::Malformed(part.to_owned()))?;
+			let invalid = || ParseError::Invalid { part: name.to_owned(), value: value.to_owned() };
+			let list = |range: std::ops::RangeInclusive<u64>| {
+				value.split(',').map(|x| x.parse::<i64>().ok().filter(|x| *x != 0 && range.contains(&x.unsigned_abs()))).collect::<Option<Vec<_>>>().ok_or_else(invalid)
+			};
+
+			match name.to_ascii_uppercase().as_str() {
+				"FREQ" => freq = Some(match value.to_ascii_uppercase().as_str() {
+					"DAILY" => Frequency::Daily,
+					"WEEKLY" => Frequency::Weekly,
+					"MONTHLY" => Frequency::Monthly,
+					"YEARLY" => Frequency::Yearly,
+					"SECONDLY" | "MINUTELY" | "HOURLY" => return Err(ParseError::Unsupported(format!("FREQ={value}"))),
+					_ => return Err(invalid()),
+				}),
+				"INTERVAL" => rule.interval = value.parse().ok().filter(|x| *x > 0).ok_or_else(invalid)?,
+				"COUNT" => rule.count = Some(value.parse().map_err(|_| invalid())?),
+				"UNTIL" => rule.until = Some(value.parse().map_err(|_| invalid())?),
+				"BYDAY" => rule.by_day = value.split(',').map(parse_by_day).collect::<Option<_>>().ok_or_else(invalid)?,
+				"BYMONTHDAY" => rule.by_month_day = list(1..=31)?.into_iter().map(|x| x as i8).collect(),
+				"BYMONTH" => rule.by_month = list(1..=12)?.into_iter().map(|x| u32::try_from(x).map_err(|_| invalid())).collect::<Result<_, _>>()?,
+				"BYSETPOS" => rule.by_set_pos = list(1..=366)?.into_iter().map(|x| x as i16).collect(),
+				"WKST" => rule.week_start = parse_weekday(value).ok_or_else(invalid)?,
+				_ => return Err(ParseError::Unsupported(name.to_owned())),
+			}
+		}
+
+		rule.freq = freq.ok_or(ParseError::Missing("FREQ"))?;
+		if rule.count.is_some() && rule.until.is_some() { return Err(ParseError::CountAndUntil); }
+		if matches!(rule.freq, Frequency::Daily | Frequency::Weekly) && rule.by_day.iter().any(|by_day| by_day.n.is_some()) {
+			return Err(ParseError::PositionalByDay);
+		}
+		Ok(rule)
+	}
+}
+
+impl fmt::Display for RRule {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let freq = match self.freq {
+			Frequency::Daily => "DAILY",
+			Frequency::Weekly => "WEEKLY",
+			Frequency::Monthly => "MONTHLY",
+			Frequency::Yearly => "YEARLY",
+		};
+		write!(f, "FREQ={freq}")?;
+		if self.interval != 1 { write!(f, ";INTERVAL={}", self.interval)?; }
+		if let Some(count) = self.count { write!(f, ";COUNT={count}")?; }
+		if let Some(until) = self.until { write!(f, ";UNTIL={until}")?; }
+		if !self.by_day.is_empty() { write!(f, ";BYDAY={}", self.by_day.iter().join(","))?; }
+		if !self.by_month_day.is_empty() { write!(f, ";BYMONTHDAY={}", self.by_month_day.iter().join(","))?; }
+		if !self.by_month.is_empty() { write!(f, ";BYMONTH={}", self.by_month.iter().join(","))?; }
+		if !self.by_set_pos.is_empty() { write!(f, ";BYSETPOS={}", self.by_set_pos.iter().join(","))?; }
+		if self.week_start != Weekday::Mon { write!(f, ";WKST={}", weekday_code(self.week_start))?; }
+		Ok(())
+	}
+}
+
+pub struct Occurrences<'a> {
+	rule: &'a RRule,
+	dtstart: NaiveDateTime,
+	with_dtstart: bool,
+	until: Option<(DateOrTime, chrono_tz::Tz)>,
+	period: i64,
+	pending: std::vec::IntoIter<NaiveDate>,
+	emitted: u32,
+	done: bool,
+}
+
+impl Iterator for Occurrences<'_> {
+	type Item = NaiveDateTime;
+
+	fn next(&mut self) -> Option<NaiveDateTime> {
+		while !self.done {
+			if self.rule.count.is_some_and(|count| self.emitted >= count) { break; }
+			if self.with_dtstart && self.emitted == 0 {
+				self.emitted += 1;
+				return Some(self.dtstart);
+			}
+
+			let Some(day) = self.pending.next() else {
+				let mut empty = 0;
+				loop {
+					let Some(days) = self.rule.period(self.dtstart.date(), self.period) else { self.done = true; break };
+					self.period += 1;
+					if !days.is_empty() { self.pending = days.into_iter(); break; }
+					empty += 1;
+					if empty >= self.rule.max_empty_periods() { self.done = true; break; }
+				}
+				continue;
+			};
+
+			let time = day.and_time(self.dtstart.time());
+			if time < self.dtstart || (self.with_dtstart && time == self.dtstart) { continue; }
+			if self.until.is_some_and(|(until, tz)| !until.not_before(time, tz)) { break; }
+			self.emitted += 1;
+			return Some(time);
+		}
+		self.done = true;
+		None
+	}
+}
+
+/// `DTSTART`, `RRULE` and `EXDATE` lines of an iCalendar event:
+///
+/// ```text
+/// DTSTART;TZID=Europe/Berlin:20240101T090000
+/// RRULE:FREQ=WEEKLY;BYDAY=MO,WE
+/// EXDATE;TZID=Europe/Berlin:20240103T090000,20240108T090000
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecurrenceSet {
+	pub dtstart: DateOrTime,
+	/// `TZID` of `DTSTART`, `None` for floating or UTC times. Local `EXDATE`s without a `TZID` are taken to be in the same zone,
+	/// ones with a different `TZID` are kept as [`DateOrTime::Utc`].
+	pub tz: Option<chrono_tz::Tz>,
+	pub rrule: RRule,
+	pub exdates: Vec<DateOrTime>,
+}
+
+impl RecurrenceSet {
+	/// Local times of the occurrences, starting with `DTSTART`. `COUNT` includes the ones removed by `EXDATE`, as the RFC says.
+	pub fn iter(&self) -> impl Iterator<Item = NaiveDateTime> + '_ {
+		let tz = self.tz.unwrap_or(chrono_tz::UTC);
+		let mut occurrences = self.rrule.iter(self.dtstart.naive());
+		occurrences.until = self.rrule.until.map(|until| (until, tz));
+		occurrences.filter(move |&time| !self.exdates.iter().any(|exdate| exdate.matches(time, tz)))
+	}
+
+	pub fn dates(&self) -> impl Iterator<Item = NaiveDate> + '_ { self.iter().map(|time| time.date()) }
+
+	/// Occurrences in `tz` (UTC if there's none). As the RFC says, repeated local times take the first offset
+	/// and ones in a DST gap are moved forward by its length, see [`DstPolicy`].
+	pub fn datetimes(&self) -> impl Iterator<Item = chrono::DateTime<chrono_tz::Tz>> + '_ {
+		let tz = self.tz.unwrap_or(chrono_tz::UTC);
+		self.iter().filter_map(move |time| DstPolicy::default().resolve(&tz, time).ok())
+	}
+}
+
+impl FromStr for RecurrenceSet {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (mut dtstart, mut tz, mut rrule, mut exdates) = (None, None, None, Vec::new());
+		let parse_tz = |params: std::str::Split<'_, char>| {
+			params.filter_map(|param| param.strip_prefix("TZID=")).next_back().map(|tzid| {
+				tzid.parse::<chrono_tz::Tz>().map_err(|_| ParseError::Invalid { part: "TZID".to_owned(), value: tzid.to_owned() })
+			}).transpose()
+		};
+
+		for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+			let (property, value) = line.split_once(':').ok_or_else(|| ParseError::Malformed(line.to_owned()))?;
+			let mut params = property.split(';');
+			let name = params.next().unwrap_or_default().to_ascii_uppercase();
+			let invalid = || ParseError::Invalid { part: name.clone(), value: value.to_owned() };
+
+			match name.as_str() {
+				"DTSTART" => {
+					tz = parse_tz(params)?;
+					dtstart = Some(value.parse().map_err(|_| invalid())?);
+				},
+				"RRULE" => rrule = Some(value.parse()?),
+				"EXDATE" => {
+					let exdate_tz = parse_tz(params)?;
+					for exdate in value.split(',') { exdates.push((exdate.parse::<DateOrTime>().map_err(|_| invalid())?, exdate_tz)); }
+				},
+				_ => return Err(ParseError::Unsupported(name)),
+			}
+		}
+
+		let dtstart = dtstart.ok_or(ParseError::Missing("DTSTART"))?;
+		// an EXDATE in some other zone is kept as the UTC instant, which gets compared in DTSTART's zone.
+		// a floating DTSTART has no instant to compare it to
+		let exdates = exdates.into_iter().map(|(exdate, exdate_tz)| match (exdate, exdate_tz) {
+			(DateOrTime::Local(local), Some(exdate_tz)) if Some(exdate_tz) != tz => {
+				let invalid = || ParseError::Invalid { part: "EXDATE".to_owned(), value: format!("TZID={}:{exdate}", exdate_tz.name()) };
+				if tz.is_none() && matches!(dtstart, DateOrTime::Local(_)) { return Err(invalid()); }
+				let time = DstPolicy::default().resolve(&exdate_tz, local).map_err(|_| invalid())?;
+				Ok(DateOrTime::Utc(time.naive_utc()))
+			},
+			(exdate, _) => Ok(exdate),
+		}).collect::<Result<_, _>>()?;
+
+		Ok(Self { dtstart, tz, rrule: rrule.ok_or(ParseError::Missing("RRULE"))?, exdates })
+	}
+}
+
+impl fmt::Display for RecurrenceSet {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let params = |time: &DateOrTime| match (time, self.tz) {
+			(DateOrTime::Date(_), _) => ";VALUE=DATE".to_owned(),
+			(DateOrTime::Local(_), Some(tz)) => format!(";TZID={}", tz.name()),
+			_ => String::new(),
+		};
+
+		writeln!(f, "DTSTART{}:{}", params(&self.dtstart), self.dtstart)?;
+		write!(f, "RRULE:{}", self.rrule)?;
+		for (params, mut exdates) in &self.exdates.iter().chunk_by(|exdate| params(exdate)) {
+			write!(f, "\nEXDATE{params}:{}", exdates.join(","))?;
+		}
+		Ok(())
+	}
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+	Some(match s.to_ascii_uppercase().as_str() {
+		"MO" => Weekday::Mon,
+		"TU" => Weekday::Tue,
+		"WE" => Weekday::Wed,
+		"TH" => Weekday::Thu,
+		"FR" => Weekday::Fri,
+		"SA" => Weekday::Sat,
+		"SU" => Weekday::Sun,
+		_ => return None,
+	})
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+	match weekday {
+		Weekday::Mon => "MO",
+		Weekday::Tue => "TU",
+		Weekday::Wed => "WE",
+		Weekday::Thu => "TH",
+		Weekday::Fri => "FR",
+		Weekday::Sat => "SA",
+		Weekday::Sun => "SU",
+	}
+}
+
+fn parse_by_day(s: &str) -> Option<ByDay> {
+	let (n, weekday) = s.split_at_checked(s.len().checked_sub(2)?)?;
+	let n = if n.is_empty() { None } else { Some(n.parse::<i8>().ok().filter(|n| *n != 0 && n.unsigned_abs() <= 53)?) };
+	Some(ByDay { n, weekday: parse_weekday(weekday)? })
+}
+
+#[test]
+fn rrule() {
+	use crate::chrono_utils::date;
+	let dates = |rule: &str, dtstart: NaiveDate, take: usize| rule.parse::<RRule>().unwrap().dates(dtstart).take(take).collect::<Vec<_>>();
+
+	// 2024-01-05 is a friday but not the last one, so only iter has it, as the first of the three
+	assert_eq!(dates("FREQ=MONTHLY;BYDAY=-1FR;COUNT=3", date(2024, 1, 5), 10), vec![date(2024, 1, 26), date(2024, 2, 23), date(2024, 3, 29)]);
+	let rule = "FREQ=MONTHLY;BYDAY=-1FR;COUNT=3".parse::<RRule>().unwrap();
+	let occurrences = rule.iter(date(2024, 1, 5).and_hms_opt(9, 0, 0).unwrap()).map(|time| time.date()).collect::<Vec<_>>();
+	assert_eq!(occurrences, vec![date(2024, 1, 5), date(2024, 1, 26), date(2024, 2, 23)]);
+	assert_eq!(
+		dates("RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;UNTIL=20240201", date(2024, 1, 2), 10),
+		vec![date(2024, 1, 2), date(2024, 1, 4), date(2024, 1, 16), date(2024, 1, 18), date(2024, 1, 30), date(2024, 2, 1)],
+	);
+	// last workday of the month
+	assert_eq!(dates("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1", date(2024, 3, 1), 2), vec![date(2024, 3, 29), date(2024, 4, 30)]);
+	// unlike Recurrence::monthly, months without the day are skipped rather than clamped
+	assert_eq!(dates("FREQ=MONTHLY;BYMONTHDAY=31;COUNT=3", date(2024, 1, 31), 10), vec![date(2024, 1, 31), date(2024, 3, 31), date(2024, 5, 31)]);
+	assert_eq!(dates("FREQ=YEARLY", date(2024, 2, 29), 2), vec![date(2024, 2, 29), date(2028, 2, 29)]);
+	assert_eq!(dates("FREQ=YEARLY;BYDAY=1MO", date(2024, 1, 1), 2), vec![date(2024, 1, 1), date(2025, 1, 6)]);
+	assert_eq!(dates("FREQ=YEARLY;BYMONTH=11;BYDAY=4TH", date(2024, 1, 1), 1), vec![date(2024, 11, 28)]);
+	assert_eq!(dates("FREQ=DAILY;BYMONTH=2;BYMONTHDAY=30", date(2024, 1, 1), 1), Vec::<NaiveDate>::new());
+	// the next friday the 29th of february is 27 years away
+	assert_eq!(dates("FREQ=DAILY;BYMONTH=2;BYMONTHDAY=29;BYDAY=FR", date(2009, 1, 1), 1), vec![date(2036, 2, 29)]);
+	assert_eq!(dates("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=29;BYDAY=FR", date(2009, 1, 1), 1), vec![date(2036, 2, 29)]);
+
+	let rule = "FREQ=MONTHLY;INTERVAL=2;UNTIL=20241231T235959Z;BYDAY=1MO,-1SU;BYMONTHDAY=1,-1;BYMONTH=1,3;BYSETPOS=1,-1;WKST=SU";
+	assert_eq!(rule.parse::<RRule>().unwrap().to_string(), rule);
+
+	assert_eq!("COUNT=3".parse::<RRule>(), Err(ParseError::Missing("FREQ")));
+	assert_eq!("FREQ=HOURLY".parse::<RRule>(), Err(ParseError::Unsupported("FREQ=HOURLY".to_owned())));
+	assert_eq!("FREQ=DAILY;BYDAY=1MO".parse::<RRule>(), Err(ParseError::PositionalByDay));
+	assert_eq!("FREQ=DAILY;COUNT=2;UNTIL=20240101".parse::<RRule>(), Err(ParseError::CountAndUntil));
+	assert!(matches!("FREQ=DAILY;BYMONTHDAY=32".parse::<RRule>(), Err(ParseError::Invalid { .. })));
+	assert!(matches!("FREQ=DAILY;BYMONTH=0".parse::<RRule>(), Err(ParseError::Invalid { .. })));
+	assert!(matches!("FREQ=MONTHLY;BYDAY=-128MO".parse::<RRule>(), Err(ParseError::Invalid { .. })));
+	assert!(matches!("FREQ=DAILY;BYMONTHDAY=-9223372036854775808".parse::<RRule>(), Err(ParseError::Invalid { .. })));
+}
+
+#[test]
+fn recurrence_set() {
+	let text = "DTSTART;TZID=Europe/Berlin:20240330T090000\nRRULE:FREQ=DAILY;COUNT=3\nEXDATE;TZID=Europe/Berlin:20240331T090000";
+	let set = text.parse::<RecurrenceSet>().unwrap();
+	assert_eq!(set.to_string(), text);
+
+	// the excluded day still counts towards COUNT, berlin switches to summer time in between
+	let utc = set.datetimes().map(|time| time.naive_utc().to_string()).collect::<Vec<_>>();
+	assert_eq!(utc, vec!["2024-03-30 08:00:00", "2024-04-01 07:00:00"]);
+
+	// 02:30 doesn't exist on the 31st, it's moved to 03:30 instead of dropped
+	let set = "DTSTART;TZID=Europe/Berlin:20240330T023000\nRRULE:FREQ=DAILY;COUNT=3".parse::<RecurrenceSet>().unwrap();
+	let local = set.datetimes().map(|time| time.naive_local().to_string()).collect::<Vec<_>>();
+	assert_eq!(local, vec!["2024-03-30 02:30:00", "2024-03-31 03:30:00", "2024-04-01 02:30:00"]);
+
+	let set = "DTSTART;VALUE=DATE:20240101\nRRULE:FREQ=WEEKLY;UNTIL=20240122\nEXDATE;VALUE=DATE:20240108".parse::<RecurrenceSet>().unwrap();
+	assert_eq!(set.dates().map(|date| date.to_string()).collect::<Vec<_>>(), vec!["2024-01-01", "2024-01-15", "2024-01-22"]);
+
+	// DTSTART comes first and counts even off the rule, unless it's excluded
+	let set = "DTSTART;VALUE=DATE:20240105\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3".parse::<RecurrenceSet>().unwrap();
+	assert_eq!(set.dates().map(|date| date.to_string()).collect::<Vec<_>>(), vec!["2024-01-05", "2024-01-26", "2024-02-23"]);
+	let set = "DTSTART;VALUE=DATE:20240105\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3\nEXDATE;VALUE=DATE:20240105".parse::<RecurrenceSet>().unwrap();
+	assert_eq!(set.dates().map(|date| date.to_string()).collect::<Vec<_>>(), vec!["2024-01-26", "2024-02-23"]);
+
+	// new york 03:00 is berlin 09:00, new york 09:00 isn't an occurrence at all
+	let text = "DTSTART;TZID=Europe/Berlin:20240101T090000\nRRULE:FREQ=DAILY;COUNT=4\nEXDATE;TZID=America/New_York:20240102T030000,20240103T090000";
+	let set = text.parse::<RecurrenceSet>().unwrap();
+	let local = set.iter().map(|time| time.to_string()).collect::<Vec<_>>();
+	assert_eq!(local, vec!["2024-01-01 09:00:00", "2024-01-03 09:00:00", "2024-01-04 09:00:00"]);
+	assert_eq!(set.to_string(), "DTSTART;TZID=Europe/Berlin:20240101T090000\nRRULE:FREQ=DAILY;COUNT=4\nEXDATE:20240102T080000Z,20240103T140000Z");
+	let set = "DTSTART;TZID=Europe/Berlin:20240101T090000\nRRULE:FREQ=DAILY;COUNT=3\nEXDATE:20240102T080000Z".parse::<RecurrenceSet>().unwrap();
+	assert_eq!(set.dates().map(|date| date.to_string()).collect::<Vec<_>>(), vec!["2024-01-01", "2024-01-03"]);
+	assert!(matches!(
+		"DTSTART:20240101T090000\nRRULE:FREQ=DAILY\nEXDATE;TZID=America/New_York:20240102T030000".parse::<RecurrenceSet>(),
+		Err(ParseError::Invalid { .. }),
+	));
+
+	assert_eq!("RRULE:FREQ=DAILY".parse::<RecurrenceSet>(), Err(ParseError::Missing("DTSTART")));
+	assert_eq!("DTSTART:20240101\nRDATE:20240102".parse::<RecurrenceSet>(), Err(ParseError::Unsupported("RDATE".to_owned())));
+}
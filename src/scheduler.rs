@@ -0,0 +1,172 @@
+use std::sync::{Arc, Mutex};
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use futures::FutureExt as _;
+use crate::{TaskGroup, time::IntoStdDuration};
+
+#[derive(Clone, Debug)]
+pub enum Schedule {
+	/// Cron expression with seconds and an optional year, `sec min hour day-of-month month day-of-week [year]`,
+	/// evaluated in `tz` so "9:30 every day" stays 9:30 local across DST changes.
+	Cron { schedule: Box<cron::Schedule>, tz: chrono_tz::Tz },
+	/// Every `period`, counted from when the previous run finished.
+	Every(std::time::Duration),
+}
+
+impl Schedule {
+	pub fn cron(expression: &str, tz: chrono_tz::Tz) -> Result<Self, cron::error::Error> {
+		Ok(Self::Cron { schedule: Box::new(expression.parse()?), tz })
+	}
+
+	pub fn every(period: impl IntoStdDuration) -> Self { Self::Every(period.into_std()) }
+
+	/// The first run strictly after `after`, `None` if the schedule has run out (cron with a year).
+	/// Periods past the end of [`DateTime`]'s range run at its very end.
+	pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+		match self {
+			Self::Cron { schedule, tz } => schedule.after(&after.with_timezone(tz)).next().map(|next| next.with_timezone(&Utc)),
+			Self::Every(period) => Some(
+				chrono::Duration::from_std(*period).ok().and_then(|period| after.checked_add_signed(period)).unwrap_or(DateTime::<Utc>::MAX_UTC),
+			),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct JobInfo {
+	pub name: String,
+	pub next_run: Option<DateTime<Utc>>,
+	pub last_run: Option<LastRun>,
+	pub running: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct LastRun {
+	pub started: DateTime<Utc>,
+	pub finished: DateTime<Utc>,
+	/// The error, if it failed or panicked.
+	pub error: Option<String>,
+}
+
+/// Runs jobs on a [`Schedule`]. A job never overlaps with itself, runs that would have started
+/// while the previous one was still going are skipped. Failures are logged like [`crate::spawn_complain`].
+#[derive(Clone, Default)]
+pub struct Scheduler {
+	tasks: TaskGroup,
+	jobs: Arc<Mutex<Vec<Arc<Mutex<JobInfo>>>>>,
+}
+
+impl Scheduler {
+	#[must_use]
+	pub fn new() -> Self { Self::default() }
+
+	#[track_caller]
+	pub fn add<T, F>(&self, name: impl Into<String>, schedule: Schedule, mut job: impl FnMut() -> F + Send + 'static)
+	where
+		F: Future<Output = anyhow::Result<T>> + Send + 'static,
+	{
+		let name = name.into();
+		let location = std::panic::Location::caller();
+		let info = Arc::new(Mutex::new(JobInfo { name: name.clone(), next_run: None, last_run: None, running: false }));
+		self.jobs.lock().unwrap().push(info.clone());
+
+		self.tasks.spawn(name.clone(), async move {
+			loop {
+				let now = Utc::now();
+				let next = schedule.next_after(now);
+				info.lock().unwrap().next_run = next;
+				let Some(next) = next else {
+					log::info!("job {name} has no runs left");
+					return Ok(());
+				};
+				crate::time::sleep(next - now).await;
+
+				let started = Utc::now();
+				info.lock().unwrap().running = true;
+				// a panic in job() itself, before there's a future, would otherwise end the job for good
+				let res = match std::panic::AssertUnwindSafe(async { job().await }).catch_unwind().await {
+					Ok(res) => res.map(drop),
+					Err(panic) => Err(anyhow::anyhow!("panicked: {}", crate::panic_message(&*panic))),
+				}.with_context(|| format!("job {name} failed"));
+				if let Err(e) = &res { crate::report_error(e, &[("job", &name), ("spawned_at", &location)]); }
+
+				let mut info = info.lock().unwrap();
+				info.running = false;
+				info.last_run = Some(LastRun { started, finished: Utc::now(), error: res.err().map(|e| format!("{e:#}")) });
+			}
+		});
+	}
+
+	pub fn jobs(&self) -> Vec<JobInfo> {
+		self.jobs.lock().unwrap().iter().map(|info| info.lock().unwrap().clone()).collect()
+	}
+
+	/// Stops every job, see [`TaskGroup::shutdown`].
+	pub async fn shutdown(&self, timeout: std::time::Duration) -> Vec<String> { self.tasks.shutdown(timeout).await }
+}
+
+#[test]
+fn next_after() {
+	use chrono::TimeZone as _;
+
+	let after = Utc.with_ymd_and_hms(2024, 3, 30, 12, 0, 0).unwrap();
+
+	// berlin switches to summer time on the night of the 31st
+	let daily = Schedule::cron("0 30 9 * * *", chrono_tz::Europe::Berlin).unwrap();
+	assert_eq!(daily.next_after(after), Some(Utc.with_ymd_and_hms(2024, 3, 31, 7, 30, 0).unwrap()));
+
+	let every = Schedule::every(std::time::Duration::from_secs(90));
+	assert_eq!(every.next_after(after), Some(Utc.with_ymd_and_hms(2024, 3, 30, 12, 1, 30).unwrap()));
+	assert_eq!(Schedule::every(std::time::Duration::MAX).next_after(after), Some(DateTime::<Utc>::MAX_UTC));
+
+	assert!(Schedule::cron("every tuesday", chrono_tz::UTC).is_err());
+}
+
+// paused, so the 5ms wait and 20ms run take exactly that long and the counts below are exact
+#[tokio::test(start_paused = true)]
+async fn scheduler() {
+	use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+	let scheduler = Scheduler::new();
+	let (running, overlapped) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+	let runs = Arc::new(AtomicU32::new(0));
+	scheduler.add("slow", Schedule::every(std::time::Duration::from_millis(5)), {
+		let (running, overlapped, runs) = (running.clone(), overlapped.clone(), runs.clone());
+		move || {
+			let (running, overlapped, runs) = (running.clone(), overlapped.clone(), runs.clone());
+			async move {
+				if running.swap(true, Ordering::SeqCst) { overlapped.store(true, Ordering::SeqCst); }
+				crate::time::sleep(std::time::Duration::from_millis(20)).await;
+				running.store(false, Ordering::SeqCst);
+				runs.fetch_add(1, Ordering::SeqCst);
+				anyhow::ensure!(runs.load(Ordering::SeqCst) > 1, "first run fails");
+				Ok(())
+			}
+		}
+	});
+
+	// runs during 5-25ms, 30-50ms and 55-75ms, the fourth is still going
+	crate::time::sleep(std::time::Duration::from_millis(90)).await;
+	let jobs = scheduler.jobs();
+	assert_eq!(runs.load(Ordering::SeqCst), 3);
+	assert!(!overlapped.load(Ordering::SeqCst));
+	assert!(jobs[0].running);
+	assert!(jobs[0].last_run.as_ref().is_some_and(|last| last.error.is_none()));
+
+	// the job keeps its schedule when calling it panics
+	let calls = Arc::new(AtomicU32::new(0));
+	scheduler.add("eager", Schedule::every(std::time::Duration::from_millis(5)), {
+		let calls = calls.clone();
+		move || {
+			assert!(calls.fetch_add(1, Ordering::SeqCst) > 0, "no future");
+			async { Ok(()) }
+		}
+	});
+	let last_error = || scheduler.jobs()[1].last_run.as_ref().map(|last| last.error.clone());
+	crate::time::sleep(std::time::Duration::from_millis(7)).await;
+	assert_eq!(last_error(), Some(Some("job eager failed: panicked: no future".to_owned())));
+	crate::time::sleep(std::time::Duration::from_millis(5)).await;
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+	assert_eq!(last_error(), Some(None));
+	scheduler.shutdown(std::time::Duration::ZERO).await;
+}
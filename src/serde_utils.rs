@@ -61,3 +61,97 @@ pub mod chrono_duration_seconds {
 		i64::serialize(&value.num_seconds(), serializer)
 	}
 }
+
+/// `[-]P{days}DT{hours}H{minutes}M{seconds}.{fraction}S` without the zero parts, "PT0S" for nothing.
+fn iso8601(nanos: i128) -> String {
+	use std::fmt::Write as _;
+
+	const SECOND: u128 = 1_000_000_000;
+	let sign = if nanos < 0 { "-" } else { "" };
+	let nanos = nanos.unsigned_abs();
+	let (days, rest) = (nanos / (86_400 * SECOND), nanos % (86_400 * SECOND));
+	let (hours, rest) = (rest / (3600 * SECOND), rest % (3600 * SECOND));
+	let (minutes, rest) = (rest / (60 * SECOND), rest % (60 * SECOND));
+	let (seconds, fraction) = (rest / SECOND, rest % SECOND);
+
+	let mut out = format!("{sign}P");
+	if days > 0 { write!(out, "{days}D").unwrap(); }
+	if rest > 0 || hours > 0 || minutes > 0 || days == 0 {
+		out.push('T');
+		if hours > 0 { write!(out, "{hours}H").unwrap(); }
+		if minutes > 0 { write!(out, "{minutes}M").unwrap(); }
+		if rest > 0 || (hours == 0 && minutes == 0) {
+			write!(out, "{seconds}").unwrap();
+			if fraction > 0 { write!(out, ".{}", format!("{fraction:09}").trim_end_matches('0')).unwrap(); }
+			out.push('S');
+		}
+	}
+	out
+}
+
+/// ISO 8601 strings like `P1DT2H30M` or `-PT0.5S`, years in the input count 365 days and months aren't accepted.
+pub mod chrono_duration_iso8601 {
+	use serde::{Deserialize, Serialize, de::Error};
+	use crate::compound::DisplayCompound as _;
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<chrono::Duration, D::Error> {
+		crate::duration_parse::parse_iso8601(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+	}
+	pub fn serialize<S: serde::Serializer>(value: &chrono::Duration, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		super::iso8601(value.total_nanos()).serialize(serializer)
+	}
+}
+
+/// [`chrono_duration_iso8601`] for [`crate::Duration`].
+#[cfg(feature = "custom_duration")]
+pub mod duration_iso8601 {
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<crate::Duration, D::Error> {
+		super::chrono_duration_iso8601::deserialize(deserializer).map(crate::Duration::from)
+	}
+	pub fn serialize<S: serde::Serializer>(value: &crate::Duration, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		super::chrono_duration_iso8601::serialize(value, serializer)
+	}
+}
+
+/// [`chrono_duration_iso8601`] for [`std::time::Duration`], negative ones are an error.
+pub mod std_duration_iso8601 {
+	use serde::{Serialize, de::Error};
+	use crate::compound::DisplayCompound as _;
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<std::time::Duration, D::Error> {
+		super::chrono_duration_iso8601::deserialize(deserializer)?.to_std().map_err(|_| D::Error::custom("negative duration"))
+	}
+	pub fn serialize<S: serde::Serializer>(value: &std::time::Duration, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		super::iso8601(value.total_nanos()).serialize(serializer)
+	}
+}
+
+#[test]
+fn iso8601_round_trip() {
+	#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+	struct Timeouts {
+		#[serde(with = "chrono_duration_iso8601")]
+		chrono: chrono::Duration,
+		#[serde(with = "std_duration_iso8601")]
+		std: std::time::Duration,
+	}
+
+	let cases = [
+		(chrono::Duration::hours(26), "P1DT2H"),
+		(chrono::Duration::minutes(90), "PT1H30M"),
+		(chrono::Duration::days(3), "P3D"),
+		(chrono::Duration::zero(), "PT0S"),
+		(chrono::Duration::milliseconds(-1500), "-PT1.5S"),
+		(chrono::Duration::days(1) + chrono::Duration::nanoseconds(1), "P1DT0.000000001S"),
+		(chrono::Duration::minutes(1) + chrono::Duration::microseconds(250), "PT1M0.00025S"),
+	];
+	for (duration, iso) in cases {
+		let json = serde_json::json!({ "chrono": iso, "std": iso.trim_start_matches('-') });
+		let timeouts = Timeouts { chrono: duration, std: duration.abs().to_std().unwrap() };
+		assert_eq!(serde_json::to_value(&timeouts).unwrap(), json);
+		assert_eq!(serde_json::from_value::<Timeouts>(json).unwrap(), timeouts);
+	}
+
+	assert_eq!(chrono_duration_iso8601::deserialize(serde_json::json!("P1W")).unwrap(), chrono::Duration::weeks(1));
+	assert!(chrono_duration_iso8601::deserialize(serde_json::json!("P1M")).is_err());
+	assert!(chrono_duration_iso8601::deserialize(serde_json::json!("1h")).is_err());
+	assert!(std_duration_iso8601::deserialize(serde_json::json!("-PT1S")).is_err());
+}
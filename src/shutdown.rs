@@ -0,0 +1,102 @@
+use futures::future;
+use crate::{CancellationToken, TaskGroup};
+
+/// The Ctrl-C/SIGTERM dance every service binary needs:
+///
+/// ```ignore
+/// let shutdown = Shutdown::new(dur!(10 sec));
+/// shutdown.spawn("server", |token| async move { token.run_until_cancelled(serve()).await.transpose() });
+/// shutdown.run().await // -> ExitCode
+/// ```
+///
+/// Once a signal arrives (or [`Shutdown::trigger`] is called) every task's token is cancelled,
+/// they get the grace period to finish, stragglers are aborted, then sentry and the logger are flushed.
+pub struct Shutdown {
+	token: CancellationToken,
+	tasks: TaskGroup,
+	grace: std::time::Duration,
+}
+
+impl Shutdown {
+	#[must_use]
+	pub fn new(grace: std::time::Duration) -> Self {
+		Self { token: CancellationToken::new(), tasks: TaskGroup::new(), grace }
+	}
+
+	/// Cancelled when shutdown starts, for tasks spawned some other way.
+	pub fn token(&self) -> CancellationToken { self.token.child_token() }
+
+	pub fn tasks(&self) -> &TaskGroup { &self.tasks }
+
+	/// Spawns a task into the group, handing it a token that gets cancelled when shutdown starts.
+	#[track_caller]
+	pub fn spawn<T, F>(&self, name: impl Into<String>, task: impl FnOnce(CancellationToken) -> F) -> tokio::task::AbortHandle
+	where
+		F: Future<Output = anyhow::Result<T>> + Send + 'static,
+	{
+		self.tasks.spawn(name, task(self.token()))
+	}
+
+	/// Starts the shutdown without waiting for a signal, e.g. after a fatal error.
+	pub fn trigger(&self) { self.token.cancel(); }
+
+	/// Waits for Ctrl-C, SIGTERM or [`Shutdown::trigger`], then shuts everything down.
+	/// Fails if some task had to be aborted.
+	pub async fn run(self) -> std::process::ExitCode {
+		tokio::select! {
+			() = signal() => log::info!("shutdown signal received, stopping {} tasks", self.tasks.len()),
+			() = self.token.cancelled() => log::info!("shutdown triggered, stopping {} tasks", self.tasks.len()),
+		}
+		self.token.cancel();
+
+		let aborted = self.tasks.shutdown(self.grace).await;
+		if !aborted.is_empty() { log::error!("aborted tasks that didn't stop within {:?}: {}", self.grace, aborted.join(", ")); }
+
+		if let Some(client) = sentry::Hub::current().client() { client.flush(Some(self.grace)); }
+		log::logger().flush();
+
+		if aborted.is_empty() { std::process::ExitCode::SUCCESS } else { std::process::ExitCode::FAILURE }
+	}
+}
+
+async fn signal() {
+	let ctrl_c = async {
+		if let Err(e) = tokio::signal::ctrl_c().await {
+			log::error!("failed to listen for ctrl-c: {e}");
+			future::pending::<()>().await;
+		}
+	};
+
+	#[cfg(unix)]
+	let terminate = async {
+		match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+			Ok(mut terminate) => { terminate.recv().await; },
+			Err(e) => {
+				log::error!("failed to listen for SIGTERM: {e}");
+				future::pending::<()>().await;
+			},
+		}
+	};
+	#[cfg(not(unix))]
+	let terminate = future::pending::<()>();
+
+	tokio::select! {
+		() = ctrl_c => {},
+		() = terminate => {},
+	}
+}
+
+#[tokio::test]
+async fn shutdown() {
+	let polite = Shutdown::new(std::time::Duration::from_millis(10));
+	polite.spawn("polite", |token| async move { token.cancelled().await; Ok(()) });
+	polite.trigger();
+	assert_eq!(polite.run().await, std::process::ExitCode::SUCCESS);
+
+	let rude = Shutdown::new(std::time::Duration::from_millis(10));
+	rude.spawn("polite", |token| async move { token.cancelled().await; Ok(()) });
+	rude.spawn("rude", |_| future::pending::<anyhow::Result<()>>());
+	let trigger = rude.token.clone();
+	tokio::spawn(async move { trigger.cancel() });
+	assert_eq!(rude.run().await, std::process::ExitCode::FAILURE);
+}
@@ -0,0 +1,132 @@
+use futures::FutureExt as _;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Restart {
+	/// Restart whenever the task stops, even if it returned `Ok`.
+	Always,
+	/// Restart on `Err` or panic, stop once it returns `Ok`.
+	#[default]
+	OnError,
+}
+
+#[derive(Clone, Debug, smart_default::SmartDefault)]
+pub struct RestartPolicy {
+	pub restart: Restart,
+	/// `None` restarts forever.
+	pub max_restarts: Option<u32>,
+	#[default(std::time::Duration::from_millis(100))]
+	pub initial_backoff: std::time::Duration,
+	#[default(std::time::Duration::from_secs(60))]
+	pub max_backoff: std::time::Duration,
+	#[default(2.)]
+	pub backoff_factor: f64,
+}
+
+impl RestartPolicy {
+	/// How long to wait before restart number `restart` (0-based).
+	pub fn backoff(&self, restart: u32) -> std::time::Duration {
+		let secs = self.initial_backoff.as_secs_f64() * self.backoff_factor.powi(restart.try_into().unwrap_or(i32::MAX));
+		// Duration::MAX rounds up past itself as f64, so it's not representable anymore
+		std::time::Duration::try_from_secs_f64(secs.clamp(0., self.max_backoff.as_secs_f64())).unwrap_or(self.max_backoff)
+	}
+}
+
+/// Spawns `factory()` and spawns it again according to `policy` when it fails or panics.
+/// See [`supervise`].
+pub fn spawn_supervised<T, F>(
+	name: impl Into<String>,
+	policy: RestartPolicy,
+	factory: impl FnMut() -> F + Send + 'static,
+) -> tokio::task::JoinHandle<anyhow::Result<()>>
+where
+	T: 'static,
+	F: Future<Output = anyhow::Result<T>> + Send + 'static,
+{
+	tokio::task::spawn(supervise(name.into(), policy, factory))
+}
+
+/// Runs `factory()` until `policy` says to stop, logging every restart.
+/// Returns the last failure once restarts are exhausted, which also gets reported to sentry.
+///
+/// Can be handed straight to [`crate::TaskGroup::spawn`] to have the group track it.
+pub async fn supervise<T, F>(name: impl Into<String>, policy: RestartPolicy, mut factory: impl FnMut() -> F) -> anyhow::Result<()>
+where
+	F: Future<Output = anyhow::Result<T>>,
+{
+	let name = name.into();
+	let mut restarts = 0;
+	loop {
+		// the factory itself may panic too, not just the future it returns
+		let failure = match std::panic::AssertUnwindSafe(async { factory().await }).catch_unwind().await {
+			Ok(Ok(_)) if policy.restart == Restart::OnError => return Ok(()),
+			Ok(Ok(_)) => anyhow::anyhow!("task {name} exited"),
+			Ok(Err(e)) => e,
+			Err(panic) => anyhow::anyhow!("task {name} panicked: {}", crate::panic_message(&*panic)),
+		};
+
+		if policy.max_restarts.is_some_and(|max| restarts >= max) {
+			let e = failure.context(format!("task {name} gave up after {restarts} restarts"));
+			#[cfg(all(not(target_arch = "wasm32"), not(debug_assertions)))]
+			sentry::with_scope(|scope| scope.set_tag("task", &name), || sentry::capture_message(&format!("{e:#}"), sentry::Level::Error));
+			log::error!("{e:?}");
+			return Err(e);
+		}
+
+		let backoff = policy.backoff(restarts);
+		restarts += 1;
+		log::warn!("task {name} stopped, restart #{restarts} in {backoff:?}: {failure:?}");
+		tokio::time::sleep(backoff).await;
+	}
+}
+
+#[test]
+fn backoff() {
+	let policy = RestartPolicy { max_backoff: std::time::Duration::from_secs(1), ..crate::default() };
+	assert_eq!(policy.backoff(0), std::time::Duration::from_millis(100));
+	assert_eq!(policy.backoff(2), std::time::Duration::from_millis(400));
+	assert_eq!(policy.backoff(10), std::time::Duration::from_secs(1));
+	assert_eq!(policy.backoff(u32::MAX), std::time::Duration::from_secs(1));
+
+	let uncapped = RestartPolicy { max_backoff: std::time::Duration::MAX, ..crate::default() };
+	assert_eq!(uncapped.backoff(2), std::time::Duration::from_millis(400));
+	assert_eq!(uncapped.backoff(u32::MAX), std::time::Duration::MAX);
+	let negative = RestartPolicy { backoff_factor: -2., ..crate::default() };
+	assert_eq!(negative.backoff(1), std::time::Duration::ZERO);
+}
+
+#[tokio::test]
+async fn supervise_restarts() {
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	let policy = RestartPolicy { initial_backoff: std::time::Duration::from_millis(1), ..crate::default() };
+	let attempts = std::sync::Arc::new(AtomicU32::new(0));
+	let res = supervise("flaky", policy.clone(), || {
+		let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+		async move {
+			match attempt {
+				0 => anyhow::bail!("first try"),
+				1 => panic!("second try"),
+				_ => Ok(()),
+			}
+		}
+	}).await;
+	assert!(res.is_ok());
+	assert_eq!(attempts.load(Ordering::Relaxed), 3);
+
+	let attempts = AtomicU32::new(0);
+	let res = supervise("doomed", RestartPolicy { max_restarts: Some(2), ..policy }, || {
+		attempts.fetch_add(1, Ordering::Relaxed);
+		async { Err::<(), _>(anyhow::anyhow!("nope")) }
+	}).await;
+	assert!(res.unwrap_err().to_string().contains("gave up after 2 restarts"));
+	assert_eq!(attempts.load(Ordering::Relaxed), 3);
+
+	// a panic in the factory, before there's a future, is a failure like any other
+	let attempts = AtomicU32::new(0);
+	let res = supervise("eager", policy, || {
+		if attempts.fetch_add(1, Ordering::Relaxed) == 0 { panic!("no future"); }
+		async { Ok(()) }
+	}).await;
+	assert!(res.is_ok());
+	assert_eq!(attempts.load(Ordering::Relaxed), 2);
+}
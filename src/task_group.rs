@@ -0,0 +1,140 @@
+use std::{collections::HashMap, sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}}};
+
+/// Tracks named tasks so they can be listed, cancelled and awaited on shutdown.
+/// Failures and panics are logged like [`crate::spawn_complain_send`] does.
+///
+/// Cheap to clone, all clones share the same set of tasks.
+#[derive(Clone, Default)]
+pub struct TaskGroup(Arc<Inner>);
+
+#[derive(Default)]
+struct Inner {
+	next_id: AtomicU64,
+	tasks: Mutex<HashMap<u64, Task>>,
+	live: tokio::sync::watch::Sender<usize>,
+}
+
+struct Task {
+	name: String,
+	/// `None` until `tokio::task::spawn` returns.
+	abort: Option<tokio::task::AbortHandle>,
+	/// Set by [`TaskGroup::abort_all`] before there was a handle to abort.
+	aborted: bool,
+}
+
+// lives inside the spawned future, so the task is forgotten whether it finishes, fails or gets aborted
+struct Forget {
+	group: Arc<Inner>,
+	id: u64,
+}
+
+impl Drop for Forget {
+	fn drop(&mut self) {
+		let mut tasks = self.group.tasks.lock().unwrap();
+		tasks.remove(&self.id);
+		self.group.live.send_replace(tasks.len());
+	}
+}
+
+impl TaskGroup {
+	#[must_use]
+	pub fn new() -> Self { Self::default() }
+
+	#[track_caller]
+	pub fn spawn<T>(&self, name: impl Into<String>, x: impl Future<Output = anyhow::Result<T>> + Send + 'static) -> tokio::task::AbortHandle {
+		let name = name.into();
+		let id = self.0.next_id.fetch_add(1, Ordering::Relaxed);
+		let forget = Forget { group: self.0.clone(), id };
+
+		// added before spawning, as the spawn can drop the future and with it `forget` right away if the runtime is shutting down,
+		// and the lock mustn't be held then
+		{
+			let mut tasks = self.0.tasks.lock().unwrap();
+			tasks.insert(id, Task { name: name.clone(), abort: None, aborted: false });
+			self.0.live.send_replace(tasks.len());
+		}
+		let site = crate::SpawnSite::caller(Some(name));
+		let abort = tokio::task::spawn(async move {
+			let _forget = forget;
+			crate::complain(async move { x.await.map(drop) }, site).await;
+		}).abort_handle();
+		// the task may already be done and forgotten
+		if let Some(task) = self.0.tasks.lock().unwrap().get_mut(&id) {
+			if task.aborted { abort.abort(); }
+			task.abort = Some(abort.clone());
+		}
+		abort
+	}
+
+	/// Names of the tasks that are still running.
+	pub fn live(&self) -> Vec<String> {
+		self.0.tasks.lock().unwrap().values().map(|task| task.name.clone()).collect()
+	}
+
+	pub fn len(&self) -> usize { self.0.tasks.lock().unwrap().len() }
+	pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+	pub fn abort_all(&self) {
+		for task in self.0.tasks.lock().unwrap().values_mut() {
+			match &task.abort {
+				Some(abort) => abort.abort(),
+				None => task.aborted = true,
+			}
+		}
+	}
+
+	/// Waits until every task, including ones spawned while waiting, is done.
+	pub async fn join(&self) {
+		let _ = self.0.live.subscribe().wait_for(|live| *live == 0).await;
+	}
+
+	/// Gives the tasks `timeout` to finish on their own, then aborts the rest.
+	/// Returns the names of the tasks that had to be aborted.
+	pub async fn shutdown(&self, timeout: std::time::Duration) -> Vec<String> {
+		if tokio::time::timeout(timeout, self.join()).await.is_ok() { return Vec::new(); }
+
+		let stragglers = self.live();
+		self.abort_all();
+		self.join().await;
+		stragglers
+	}
+}
+
+#[tokio::test]
+async fn task_group() {
+	let _capture = crate::logger::capture();
+	let group = TaskGroup::new();
+
+	group.spawn("quick", async { Ok(()) });
+	group.spawn("failing", async { Err::<(), _>(anyhow::anyhow!("boom")) });
+	group.spawn("stuck", std::future::pending::<anyhow::Result<()>>());
+	assert_eq!(group.len(), 3);
+
+	tokio::task::yield_now().await;
+	assert_eq!(group.live(), vec!["stuck".to_owned()]);
+	crate::assert_logged!(log::Level::Error, contains "task failing spawned at");
+	crate::assert_logged!(log::Level::Error, contains "boom");
+
+	assert_eq!(group.shutdown(std::time::Duration::from_millis(10)).await, vec!["stuck".to_owned()]);
+	assert!(group.is_empty());
+}
+
+#[test]
+fn spawn_during_runtime_shutdown() {
+	struct SpawnOnDrop(TaskGroup);
+	impl Drop for SpawnOnDrop {
+		fn drop(&mut self) { self.0.spawn("late", async { Ok(()) }); }
+	}
+
+	let group = TaskGroup::new();
+	let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+	let on_drop = SpawnOnDrop(group.clone());
+	runtime.spawn(async move {
+		let _on_drop = on_drop;
+		std::future::pending::<()>().await;
+	});
+	runtime.block_on(tokio::task::yield_now());
+	// dropping the runtime drops the pending task, which spawns onto the closed runtime
+	drop(runtime);
+	assert!(group.is_empty());
+}
@@ -0,0 +1,308 @@
+//! `sleep`, `interval` and `timeout` that work the same on native (tokio) and wasm32 (`setTimeout`).
+
+use std::{pin::Pin, task::{Context, Poll}};
+use futures::Stream;
+
+/// Durations these functions accept. Negative durations count as zero.
+pub trait IntoStdDuration {
+	fn into_std(self) -> std::time::Duration;
+}
+
+impl IntoStdDuration for std::time::Duration {
+	fn into_std(self) -> std::time::Duration { self }
+}
+
+impl IntoStdDuration for chrono::Duration {
+	fn into_std(self) -> std::time::Duration { self.to_std().unwrap_or_default() }
+}
+
+#[cfg(feature = "custom_duration")]
+impl IntoStdDuration for crate::Duration {
+	fn into_std(self) -> std::time::Duration { self.to_std().unwrap_or_default() }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("deadline has elapsed")]
+pub struct Elapsed;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Sleep(Pin<Box<tokio::time::Sleep>>);
+
+#[cfg(target_arch = "wasm32")]
+pub struct Sleep(gloo_timers::future::TimeoutFuture);
+
+pub fn sleep(duration: impl IntoStdDuration) -> Sleep {
+	#[cfg(not(target_arch = "wasm32"))]
+	{ Sleep(Box::pin(tokio::time::sleep(duration.into_std()))) }
+	#[cfg(target_arch = "wasm32")]
+	{ Sleep(gloo_timers::future::TimeoutFuture::new(wasm_millis(duration.into_std()))) }
+}
+
+impl Future for Sleep {
+	type Output = ();
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> { Pin::new(&mut self.0).poll(cx) }
+}
+
+/// Ticks every `period`, the first tick comes after one period.
+/// Late ticks are delayed rather than bunched up, like `setInterval`.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Interval(tokio::time::Interval);
+
+/// Ticks every `period`, the first tick comes after one period.
+/// Late ticks are delayed rather than bunched up, like `setInterval`.
+#[cfg(target_arch = "wasm32")]
+pub struct Interval(gloo_timers::future::IntervalStream);
+
+/// Periods below a millisecond, including zero and negative ones, count as a millisecond.
+pub fn interval(period: impl IntoStdDuration) -> Interval {
+	let period = period.into_std().max(std::time::Duration::from_millis(1));
+	#[cfg(not(target_arch = "wasm32"))]
+	{
+		let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+		Interval(interval)
+	}
+	#[cfg(target_arch = "wasm32")]
+	{ Interval(gloo_timers::future::IntervalStream::new(wasm_millis(period))) }
+}
+
+impl Interval {
+	pub async fn tick(&mut self) { futures::StreamExt::next(self).await; }
+}
+
+impl Stream for Interval {
+	type Item = ();
+
+	#[cfg(not(target_arch = "wasm32"))]
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
+		self.0.poll_tick(cx).map(|_| Some(()))
+	}
+
+	#[cfg(target_arch = "wasm32")]
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
+		Pin::new(&mut self.0).poll_next(cx)
+	}
+}
+
+pub async fn timeout<F: Future>(duration: impl IntoStdDuration, future: F) -> Result<F::Output, Elapsed> {
+	#[cfg(not(target_arch = "wasm32"))]
+	{ tokio::time::timeout(duration.into_std(), future).await.map_err(|_| Elapsed) }
+	#[cfg(target_arch = "wasm32")]
+	{
+		match futures::future::select(std::pin::pin!(future), sleep(duration)).await {
+			futures::future::Either::Left((output, _)) => Ok(output),
+			futures::future::Either::Right(((), _)) => Err(Elapsed),
+		}
+	}
+}
+
+// setTimeout takes an i32 of milliseconds and fires immediately on anything bigger
+#[cfg(target_arch = "wasm32")]
+fn wasm_millis(duration: std::time::Duration) -> u32 {
+	duration.as_millis().min(i32::MAX as u128) as u32
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn time() {
+	sleep(std::time::Duration::from_millis(1)).await;
+	sleep(chrono::Duration::milliseconds(-5)).await;
+
+	assert_eq!(timeout(std::time::Duration::from_millis(50), async { 5 }).await, Ok(5));
+	assert_eq!(timeout(std::time::Duration::from_millis(1), futures::future::pending::<()>()).await, Err(Elapsed));
+
+	let mut interval = interval(std::time::Duration::from_millis(1));
+	interval.tick().await;
+	assert_eq!(futures::StreamExt::next(&mut interval).await, Some(()));
+
+	let mut zero = self::interval(chrono::Duration::milliseconds(-5));
+	zero.tick().await;
+}
+
+/// Time-based adapters for any [`Stream`], built on [`sleep`] and [`interval`] so they work on wasm32 too.
+pub trait StreamTimeExt: Stream + Sized {
+	/// Emits an item only once `period` has passed without a newer one, the last item is flushed when the stream ends.
+	fn debounce(self, period: impl IntoStdDuration) -> Debounce<Self> {
+		Debounce { stream: Box::pin(self), period: period.into_std(), pending: None, sleep: None, done: false }
+	}
+
+	/// Emits an item, then drops everything else for `period`.
+	fn throttle(self, period: impl IntoStdDuration) -> Throttle<Self> {
+		Throttle { stream: Box::pin(self), period: period.into_std(), cooldown: None }
+	}
+
+	/// Emits the latest item once every `period`, if there's been a new one since the last tick.
+	fn sample(self, period: impl IntoStdDuration) -> Sample<Self> {
+		Sample { stream: Box::pin(self), interval: interval(period), latest: None, done: false }
+	}
+
+	/// Groups items into batches of up to `size`, emitting a partial batch once `timeout` has passed since its first item.
+	fn batch_timeout(self, size: usize, timeout: impl IntoStdDuration) -> BatchTimeout<Self> {
+		BatchTimeout { stream: Box::pin(self), size: size.max(1), timeout: timeout.into_std(), batch: Vec::new(), deadline: None, done: false }
+	}
+}
+
+impl<S: Stream> StreamTimeExt for S {}
+
+pub struct Debounce<S: Stream> {
+	stream: Pin<Box<S>>,
+	period: std::time::Duration,
+	pending: Option<S::Item>,
+	sleep: Option<Sleep>,
+	done: bool,
+}
+
+// the stream is boxed and items are never pinned
+impl<S: Stream> Unpin for Debounce<S> {}
+
+impl<S: Stream> Stream for Debounce<S> {
+	type Item = S::Item;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+		let this = self.get_mut();
+		while !this.done {
+			match this.stream.as_mut().poll_next(cx) {
+				Poll::Ready(Some(item)) => {
+					this.pending = Some(item);
+					this.sleep = Some(sleep(this.period));
+				},
+				Poll::Ready(None) => this.done = true,
+				Poll::Pending => break,
+			}
+		}
+		if this.done { return Poll::Ready(this.pending.take()); }
+
+		let Some(sleep) = &mut this.sleep else { return Poll::Pending };
+		std::task::ready!(Pin::new(sleep).poll(cx));
+		this.sleep = None;
+		Poll::Ready(this.pending.take())
+	}
+}
+
+pub struct Throttle<S: Stream> {
+	stream: Pin<Box<S>>,
+	period: std::time::Duration,
+	cooldown: Option<Sleep>,
+}
+
+impl<S: Stream> Stream for Throttle<S> {
+	type Item = S::Item;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+		let this = self.get_mut();
+		loop {
+			if let Some(cooldown) = &mut this.cooldown && Pin::new(cooldown).poll(cx).is_ready() {
+				this.cooldown = None;
+			}
+
+			match std::task::ready!(this.stream.as_mut().poll_next(cx)) {
+				Some(item) if this.cooldown.is_none() => {
+					this.cooldown = Some(sleep(this.period));
+					return Poll::Ready(Some(item));
+				},
+				Some(_) => continue,
+				None => return Poll::Ready(None),
+			}
+		}
+	}
+}
+
+pub struct Sample<S: Stream> {
+	stream: Pin<Box<S>>,
+	interval: Interval,
+	latest: Option<S::Item>,
+	done: bool,
+}
+
+impl<S: Stream> Unpin for Sample<S> {}
+
+impl<S: Stream> Stream for Sample<S> {
+	type Item = S::Item;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+		let this = self.get_mut();
+		loop {
+			while !this.done {
+				match this.stream.as_mut().poll_next(cx) {
+					Poll::Ready(Some(item)) => this.latest = Some(item),
+					Poll::Ready(None) => this.done = true,
+					Poll::Pending => break,
+				}
+			}
+			if this.done { return Poll::Ready(this.latest.take()); }
+
+			std::task::ready!(Pin::new(&mut this.interval).poll_next(cx));
+			if let Some(item) = this.latest.take() { return Poll::Ready(Some(item)); }
+		}
+	}
+}
+
+pub struct BatchTimeout<S: Stream> {
+	stream: Pin<Box<S>>,
+	size: usize,
+	timeout: std::time::Duration,
+	batch: Vec<S::Item>,
+	deadline: Option<Sleep>,
+	done: bool,
+}
+
+impl<S: Stream> Unpin for BatchTimeout<S> {}
+
+impl<S: Stream> Stream for BatchTimeout<S> {
+	type Item = Vec<S::Item>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<S::Item>>> {
+		let this = self.get_mut();
+		while !this.done {
+			match this.stream.as_mut().poll_next(cx) {
+				Poll::Ready(Some(item)) => {
+					if this.batch.is_empty() { this.deadline = Some(sleep(this.timeout)); }
+					this.batch.push(item);
+					if this.batch.len() >= this.size {
+						this.deadline = None;
+						return Poll::Ready(Some(std::mem::take(&mut this.batch)));
+					}
+				},
+				Poll::Ready(None) => this.done = true,
+				Poll::Pending => break,
+			}
+		}
+		if this.done {
+			this.deadline = None;
+			return Poll::Ready((!this.batch.is_empty()).then(|| std::mem::take(&mut this.batch)));
+		}
+
+		let Some(deadline) = &mut this.deadline else { return Poll::Pending };
+		std::task::ready!(Pin::new(deadline).poll(cx));
+		this.deadline = None;
+		Poll::Ready(Some(std::mem::take(&mut this.batch)))
+	}
+}
+
+// paused, so the clock only moves when every task is waiting on a timer and the timings are exact
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test(start_paused = true)]
+async fn stream_time_ext() {
+	use futures::StreamExt as _;
+
+	// (ms to wait before the item, item)
+	fn timed(items: Vec<(u64, i32)>) -> impl Stream<Item = i32> {
+		futures::stream::iter(items).then(|(wait, item)| async move {
+			sleep(std::time::Duration::from_millis(wait)).await;
+			item
+		})
+	}
+	let ms = std::time::Duration::from_millis;
+
+	let debounced = timed(vec![(0, 1), (0, 2), (0, 3), (80, 4), (0, 5)]).debounce(ms(40)).collect::<Vec<_>>().await;
+	assert_eq!(debounced, vec![3, 5]);
+
+	let throttled = timed(vec![(0, 1), (0, 2), (0, 3), (80, 4), (0, 5)]).throttle(ms(40)).collect::<Vec<_>>().await;
+	assert_eq!(throttled, vec![1, 4]);
+
+	let sampled = timed(vec![(0, 1), (0, 2), (100, 3)]).sample(ms(40)).collect::<Vec<_>>().await;
+	assert_eq!(sampled, vec![2, 3]);
+
+	let batched = timed(vec![(0, 1), (0, 2), (0, 3), (80, 4)]).batch_timeout(2, ms(40)).collect::<Vec<_>>().await;
+	assert_eq!(batched, vec![vec![1, 2], vec![3], vec![4]]);
+}